@@ -0,0 +1,89 @@
+#![allow(clippy::needless_return)]
+
+use rust_connect_4::clock::TimeControl;
+use rust_connect_4::lobby;
+use rust_connect_4::lobby::ServerSettings;
+use std::net::TcpListener;
+use std::process::exit;
+use std::time::Duration;
+
+
+const DEFAULT_PORT: u16 = 7878;
+const USAGE: &str = "Usage: server [--port N] [--idle-timeout SECONDS] [--max-wait SECONDS]
+              [--clock CONTROL]";
+
+
+// Run the game server until it's stopped. Clients connect with `rust-connect-4 connect`.
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (port, settings) = match parse_options(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
+    let listener: TcpListener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("Couldn't listen on port {port}: {error}");
+            exit(1);
+        }
+    };
+    println!("Serving Connect-4 games on port {port}.");
+    lobby::serve(listener, settings);
+}
+
+
+// Parse the command line arguments (excluding the program name) into the port and settings.
+fn parse_options(args: &[String]) -> Result<(u16, ServerSettings), String> {
+    let mut port: u16 = DEFAULT_PORT;
+    let mut settings: ServerSettings = ServerSettings::default();
+    for pair in args.chunks(2) {
+        let flag: &str = pair[0].as_str();
+        let value: &String = pair.get(1).ok_or(format!("Missing value for {flag}"))?;
+        if flag == "--clock" {
+            settings.time_control = Some(TimeControl::parse(value)
+                    .ok_or(format!("Invalid value for {flag}: {value}"))?);
+            continue;
+        }
+        let number: u64 = value.parse::<u64>()
+                .map_err(|_| format!("Invalid value for {flag}: {value}"))?;
+        match flag {
+            "--port" => port = u16::try_from(number)
+                    .map_err(|_| format!("Invalid value for {flag}: {value}"))?,
+            "--idle-timeout" => settings.idle_timeout = Duration::from_secs(number),
+            "--max-wait" => settings.max_wait = Duration::from_secs(number),
+            _ => return Err(format!("Unknown option: {flag}")),
+        }
+    }
+    return Ok((port, settings));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|arg| String::from(*arg)).collect();
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(parse_options(&[]), Ok((DEFAULT_PORT, ServerSettings::default())));
+        let options = parse_options(&args(&["--port", "9000", "--max-wait", "60"]));
+        let (port, settings) = options.unwrap();
+        assert_eq!(port, 9000);
+        assert_eq!(settings.max_wait, Duration::from_secs(60));
+        let (_, settings) = parse_options(&args(&["--clock", "60+1"])).unwrap();
+        let increment = Duration::from_secs(1);
+        assert_eq!(settings.time_control,
+                   Some(TimeControl::Fischer { base: Duration::from_secs(60), increment }));
+        assert!(parse_options(&args(&["--clock", "soon"])).is_err());
+        assert!(parse_options(&args(&["--port", "70000"])).is_err());
+        assert!(parse_options(&args(&["--port"])).is_err());
+        assert!(parse_options(&args(&["--colour", "red"])).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+// Game clocks.
+//
+// Each player has a clock that runs during their turns. A time control sets how much time they
+// start with and how it's topped up, and is written the way chess PGN's TimeControl header
+// writes it:
+//
+//     300      Sudden death: 300 seconds for the whole game.
+//     300+5    Fischer: 300 seconds, plus 5 more after every move.
+//     1/10     Per move: 10 seconds for every move, with nothing carried over.
+//
+// A player whose clock runs out loses on time.
+
+use crate::game::Player;
+use std::time::Duration;
+use std::time::Instant;
+
+
+const THINK_TIME_PERCENT: u32 = 75;  // The share of a move's time spent thinking.
+
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum TimeControl {
+    SuddenDeath(Duration),
+    Fischer { base: Duration, increment: Duration },
+    PerMove(Duration),
+}
+
+
+// The two players' clocks. At most one runs at a time.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: [Duration; 2],  // Player 1's and player 2's time, as of their last move.
+    running: Option<(Player, Instant)>,  // Whose clock is running and since when.
+}
+
+
+impl TimeControl {
+    // Parse a time control in the PGN style described above, or return None if it isn't one.
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        let seconds = |text: &str| text.parse::<u64>().ok().map(Duration::from_secs);
+        if let Some((moves, limit)) = text.split_once('/') {
+            return if moves == "1" { seconds(limit).map(TimeControl::PerMove) } else { None };
+        }
+        if let Some((base, increment)) = text.split_once('+') {
+            let (base, increment) = (seconds(base)?, seconds(increment)?);
+            return Some(TimeControl::Fischer { base, increment });
+        }
+        return seconds(text).map(TimeControl::SuddenDeath);
+    }
+
+
+    pub fn format(&self) -> String {
+        return match self {
+            TimeControl::SuddenDeath(base) => format!("{}", base.as_secs()),
+            TimeControl::Fischer { base, increment } =>
+                    format!("{}+{}", base.as_secs(), increment.as_secs()),
+            TimeControl::PerMove(limit) => format!("1/{}", limit.as_secs()),
+        };
+    }
+
+
+    // Return the time each player starts with.
+    pub fn initial_time(&self) -> Duration {
+        return match self {
+            TimeControl::SuddenDeath(base) => *base,
+            TimeControl::Fischer { base, .. } => *base,
+            TimeControl::PerMove(limit) => *limit,
+        };
+    }
+}
+
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let initial: Duration = control.initial_time();
+        return Clock { control, remaining: [initial, initial], running: None };
+    }
+
+
+    pub fn control(&self) -> TimeControl {
+        return self.control;
+    }
+
+
+    // Return the time the player has left, counting the turn in progress if it's theirs.
+    pub fn remaining(&self, player: Player) -> Duration {
+        let remaining: Duration = self.remaining[index(player)];
+        return match self.running {
+            Some((running, since)) if running == player =>
+                    remaining.saturating_sub(since.elapsed()),
+            _ => remaining,
+        };
+    }
+
+
+    // Return the time each player has left, player 1's first.
+    pub fn times(&self) -> [Duration; 2] {
+        return [self.remaining(Player::One), self.remaining(Player::Two)];
+    }
+
+
+    // Return whether the player's time has run out.
+    pub fn is_flagged(&self, player: Player) -> bool {
+        return self.remaining(player).is_zero();
+    }
+
+
+    // Return how long the player should think about their move, given roughly how many more
+    // moves they'll make after it, so that they don't run out. Some time is kept in hand for
+    // the moves themselves.
+    pub fn think_time(&self, player: Player, moves_left: usize) -> Duration {
+        let remaining: Duration = self.remaining(player);
+        let share: Duration = match self.control {
+            TimeControl::SuddenDeath(_) => remaining / (moves_left as u32 + 1),
+            TimeControl::Fischer { increment, .. } =>
+                    (remaining / (moves_left as u32 + 1) + increment).min(remaining),
+            TimeControl::PerMove(_) => remaining,
+        };
+        return share * THINK_TIME_PERCENT / 100;
+    }
+
+
+    // Start the player's clock at the start of their turn, stopping the other clock.
+    pub fn start(&mut self, player: Player) {
+        if let Some((running, _)) = self.running {
+            self.charge(running);
+        }
+        self.running = Some((player, Instant::now()));
+    }
+
+
+    // Stop the player's clock at the end of their turn, and return whether they made it in
+    // time. A player who did gets the time control's top-up for the move.
+    pub fn stop(&mut self, player: Player) -> bool {
+        self.charge(player);
+        if self.is_flagged(player) {
+            return false;
+        }
+        let remaining: &mut Duration = &mut self.remaining[index(player)];
+        match self.control {
+            TimeControl::SuddenDeath(_) => {},
+            TimeControl::Fischer { increment, .. } => *remaining += increment,
+            TimeControl::PerMove(limit) => *remaining = limit,
+        }
+        return true;
+    }
+
+
+    // Take the time since the player's clock started off their time, and stop it.
+    fn charge(&mut self, player: Player) {
+        if let Some((running, since)) = self.running {
+            if running == player {
+                let remaining: &mut Duration = &mut self.remaining[index(player)];
+                *remaining = remaining.saturating_sub(since.elapsed());
+                self.running = None;
+            }
+        }
+    }
+}
+
+
+// Format a time left on a clock as minutes and seconds, with tenths under ten seconds.
+pub fn format_time(time: Duration) -> String {
+    let seconds: u64 = time.as_secs();
+    if seconds < 10 {
+        return format!("0:{:02}.{}", seconds, time.subsec_millis() / 100);
+    }
+    return format!("{}:{:02}", seconds / 60, seconds % 60);
+}
+
+
+fn index(player: Player) -> usize {
+    return match player {
+        Player::One => 0,
+        Player::Two => 1,
+    };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_parse_and_format() {
+        let controls = [
+            ("300", TimeControl::SuddenDeath(Duration::from_secs(300))),
+            ("180+2", TimeControl::Fischer {
+                base: Duration::from_secs(180),
+                increment: Duration::from_secs(2),
+            }),
+            ("1/15", TimeControl::PerMove(Duration::from_secs(15))),
+        ];
+        for (text, control) in controls {
+            assert_eq!(TimeControl::parse(text), Some(control));
+            assert_eq!(control.format(), text);
+        }
+        assert_eq!(TimeControl::parse("40/9000"), None);
+        assert_eq!(TimeControl::parse("5m"), None);
+        assert_eq!(TimeControl::parse("-"), None);
+    }
+
+    #[test]
+    fn test_fischer_increment() {
+        let control = TimeControl::Fischer {
+            base: Duration::from_secs(60),
+            increment: Duration::from_secs(5),
+        };
+        let mut clock = Clock::new(control);
+        clock.start(Player::One);
+        assert!(clock.stop(Player::One));
+        assert!(clock.remaining(Player::One) > Duration::from_secs(64));
+        assert_eq!(clock.remaining(Player::Two), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_running_out() {
+        let mut clock = Clock::new(TimeControl::SuddenDeath(Duration::from_millis(20)));
+        clock.start(Player::Two);
+        thread::sleep(Duration::from_millis(30));
+        assert!(clock.is_flagged(Player::Two));
+        assert!(!clock.stop(Player::Two));
+        assert!(!clock.is_flagged(Player::One));
+    }
+
+    #[test]
+    fn test_per_move_limit_resets() {
+        let mut clock = Clock::new(TimeControl::PerMove(Duration::from_secs(10)));
+        clock.start(Player::One);
+        thread::sleep(Duration::from_millis(20));
+        assert!(clock.remaining(Player::One) < Duration::from_secs(10));
+        assert!(clock.stop(Player::One));
+        assert_eq!(clock.remaining(Player::One), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_think_time() {
+        let clock = Clock::new(TimeControl::SuddenDeath(Duration::from_secs(100)));
+        assert_eq!(clock.think_time(Player::One, 9), Duration::from_millis(7_500));
+        let clock = Clock::new(TimeControl::PerMove(Duration::from_secs(4)));
+        assert_eq!(clock.think_time(Player::Two, 20), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(Duration::from_secs(300)), "5:00");
+        assert_eq!(format_time(Duration::from_secs(61)), "1:01");
+        assert_eq!(format_time(Duration::from_millis(9_450)), "0:09.4");
+    }
+}
@@ -0,0 +1,245 @@
+use crate::game::GameConfig;
+use crate::game::Player;
+use crate::mcts::Budget;
+use crate::mcts::MctsPlayer;
+use crate::negamax::NegamaxPlayer;
+use crate::position::Position;
+use crate::rng::Rng;
+use crate::solver::MOVE_TIME_LIMIT;
+use crate::solver::Solver;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+
+
+const EXPERT_MCTS_ITERATIONS: usize = 50_000;
+
+
+// How well the computer plays, from making plenty of mistakes to not making any.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+
+pub const DIFFICULTIES: [Difficulty; 5] = [
+    Difficulty::Beginner,
+    Difficulty::Easy,
+    Difficulty::Medium,
+    Difficulty::Hard,
+    Difficulty::Expert,
+];
+
+
+// How a difficulty level plays: how far ahead it searches, how often it plays a random move
+// instead, and how often it overlooks a win or a threat it has to block.
+struct Settings {
+    depth: Option<usize>,  // None for the expert, which solves or uses tree search instead.
+    random_move_chance: f64,
+    missed_threat_chance: f64,
+}
+
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        };
+    }
+
+
+    // Return the difficulty with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        return DIFFICULTIES.iter().copied().find(|level| level.name().eq_ignore_ascii_case(name));
+    }
+
+
+    fn settings(&self) -> Settings {
+        let (depth, random_move_chance, missed_threat_chance) = match self {
+            Difficulty::Beginner => (Some(1), 0.35, 0.6),
+            Difficulty::Easy => (Some(2), 0.2, 0.3),
+            Difficulty::Medium => (Some(4), 0.08, 0.1),
+            Difficulty::Hard => (Some(7), 0.0, 0.0),
+            Difficulty::Expert => (None, 0.0, 0.0),
+        };
+        return Settings { depth, random_move_chance, missed_threat_chance };
+    }
+}
+
+
+// A computer player that plays at a difficulty level. The lower levels search a few moves ahead
+// and sometimes play a random move or overlook an immediate win or loss. The expert level
+// plays perfectly on the standard board using the solver once it can solve the position in
+// time, and otherwise uses Monte Carlo tree search: early in the game, and on custom boards,
+// where the solver doesn't work.
+pub struct DifficultyPlayer {
+    difficulty: Difficulty,
+    rng: Rng,
+    solver: Option<Solver>,  // Created on first use, since its table is large.
+}
+
+
+impl DifficultyPlayer {
+    pub fn new(difficulty: Difficulty) -> Self {
+        return DifficultyPlayer { difficulty, rng: Rng::from_time(), solver: None };
+    }
+
+
+    // Create a player whose mistakes are always the same for the same seed.
+    pub fn with_seed(difficulty: Difficulty, seed: u64) -> Self {
+        return DifficultyPlayer { difficulty, rng: Rng::new(seed), solver: None };
+    }
+
+
+    pub fn difficulty(&self) -> Difficulty {
+        return self.difficulty;
+    }
+
+
+    // Return the column to play for the player to move, or None if the board is full.
+    pub fn choose_move(&mut self, position: &Position) -> Option<usize> {
+        return self.choose(position, None);
+    }
+
+
+    // Return the column to play as `choose_move` does, thinking for no longer than the given
+    // time. The lower levels search less deeply if they have to, and the expert spends half of
+    // the time trying to solve the position and the rest on tree search if it can't.
+    pub fn choose_move_within(&mut self, position: &Position, think_time: Duration)
+            -> Option<usize> {
+        return self.choose(position, Some(think_time));
+    }
+
+
+    fn choose(&mut self, position: &Position, think_time: Option<Duration>) -> Option<usize> {
+        let start: Instant = Instant::now();
+        let settings: Settings = self.difficulty.settings();
+        let cols: Vec<usize> = (0..position.config().width())
+                .filter(|col| position.can_play(*col))
+                .collect();
+        if cols.is_empty() {
+            return None;
+        }
+        let player: Player = position.to_move();
+        let mut urgent: Vec<usize> = position.winning_columns(player);
+        urgent.extend(position.winning_columns(player.other()));
+        if !urgent.is_empty() {
+            if self.rng.chance(settings.missed_threat_chance) {
+                let others: Vec<usize> =
+                        cols.iter().copied().filter(|col| !urgent.contains(col)).collect();
+                let choices: &[usize] = if others.is_empty() { &cols } else { &others };
+                return Some(choices[self.rng.below(choices.len())]);
+            }
+        } else if self.rng.chance(settings.random_move_chance) {
+            return Some(cols[self.rng.below(cols.len())]);
+        }
+        if let Some(depth) = settings.depth {
+            let negamax: NegamaxPlayer = NegamaxPlayer::new(depth);
+            return match think_time {
+                Some(think_time) => negamax.best_move_within(position, think_time),
+                None => negamax.best_move(position),
+            };
+        }
+        if position.config() == GameConfig::default() {
+            let limit: Duration =
+                    think_time.map_or(MOVE_TIME_LIMIT, |t| (t / 2).min(MOVE_TIME_LIMIT));
+            let solver: &mut Solver = self.solver.get_or_insert_with(Solver::new);
+            if let Ok(col) = solver.best_move_within(position, limit) {
+                return Some(col);
+            }
+        }
+        let budget: Budget = match think_time {
+            Some(think_time) => Budget::Time(think_time.saturating_sub(start.elapsed())),
+            None => Budget::Iterations(EXPERT_MCTS_ITERATIONS),
+        };
+        return MctsPlayer::with_seed(budget, self.rng.next_u64()).best_move(position);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation;
+    use crate::notation::test_positions::position;
+
+    #[test]
+    fn test_names() {
+        for level in DIFFICULTIES {
+            assert_eq!(Difficulty::from_name(level.name()), Some(level));
+        }
+        assert_eq!(Difficulty::from_name("Hard"), Some(Difficulty::Hard));
+        assert_eq!(Difficulty::from_name("impossible"), None);
+    }
+
+    #[test]
+    fn test_beginner_sometimes_misses_block() {
+        // Player 2 must block the vertical line in column 1.
+        let misses = (0..50)
+                .map(|seed| {
+                    let mut player = DifficultyPlayer::with_seed(Difficulty::Beginner, seed);
+                    return player.choose_move(&position("12121"));
+                })
+                .filter(|col| *col != Some(0))
+                .count();
+        assert!(misses > 0 && misses < 50, "{misses}");
+    }
+
+    #[test]
+    fn test_hard_always_blocks() {
+        for seed in 0..20 {
+            let mut player = DifficultyPlayer::with_seed(Difficulty::Hard, seed);
+            assert_eq!(player.choose_move(&position("12121")), Some(0));
+        }
+    }
+
+    #[test]
+    fn test_expert_takes_win() {
+        let mut player = DifficultyPlayer::with_seed(Difficulty::Expert, 1);
+        assert_eq!(player.choose_move(&position("121212")), Some(0));
+    }
+
+    #[test]
+    fn test_expert_on_custom_board() {
+        let game = notation::parse(GameConfig::new(8, 7, 4).unwrap(), "12121").unwrap();
+        let mut player = DifficultyPlayer::with_seed(Difficulty::Expert, 1);
+        assert_eq!(player.choose_move(game.position()), Some(0));
+    }
+
+    #[test]
+    fn test_expert_answers_in_opening() {
+        let mut player = DifficultyPlayer::with_seed(Difficulty::Expert, 1);
+        assert!(player.choose_move(&position("44")).is_some());
+    }
+
+    #[test]
+    fn test_think_time() {
+        for level in DIFFICULTIES {
+            let mut player = DifficultyPlayer::with_seed(level, 1);
+            let start = Instant::now();
+            let think_time = Duration::from_millis(100);
+            assert!(player.choose_move_within(&position("4"), think_time).is_some());
+            assert!(start.elapsed() < Duration::from_secs(1), "{}", level.name());
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_moves() {
+        let moves = |seed| {
+            let mut player = DifficultyPlayer::with_seed(Difficulty::Easy, seed);
+            return ["", "4", "45", "453"].map(|moves| player.choose_move(&position(moves)));
+        };
+        assert_eq!(moves(3), moves(3));
+    }
+}
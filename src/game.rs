@@ -1,147 +1,435 @@
-use crate::printer;
+use crate::position::Bitboard;
+use crate::position::Direction;
+use crate::position::Position;
 use std::cmp::max;
-use std::cmp::min;
 use std::vec::Vec;
 
 
-const BOARD_WIDTH: usize = 7;
-const BOARD_HEIGHT: usize = 6;
-const COMMAND_QUIT: &str = "q";
-const TOKEN_P1: &str = "🟡";
-const TOKEN_P2: &str = "🔴";
-const WIN_LENGTH: usize = 4;
+const DEFAULT_BOARD_WIDTH: usize = 7;
+const DEFAULT_BOARD_HEIGHT: usize = 6;
+const DEFAULT_WIN_LENGTH: usize = 4;
 
 
+#[derive(Clone)]
+#[derive(Copy)]
 #[derive(Debug)]
 #[derive(PartialEq)]
-enum GameState {
+pub enum GameState {
     InProgress,
-    WinP1,
-    WinP2,
+    Win(Player),
     Tie,
+    OutOfTime(Player),  // The player's clock ran out, so the other player wins.
+    Resigned(Player),  // The player resigned, so the other player wins.
+    DrawAgreed,  // One player offered a draw and the other accepted.
+    Aborted,  // The game was abandoned with no result.
 }
 
 
+#[derive(Clone)]
+#[derive(Copy)]
 #[derive(Debug)]
+#[derive(Eq)]
+#[derive(Hash)]
 #[derive(PartialEq)]
-enum TurnResult {
-    Valid,
-    Invalid,
+pub enum Player {
+    One,  // Player 1 goes first.
+    Two,
+}
+
+
+impl Player {
+    // Return the opponent of this player.
+    pub fn other(self) -> Player {
+        return match self {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+        };
+    }
+}
+
+
+// The contents of one cell of the board.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Cell {
+    Empty,
+    Occupied(Player),
+}
+
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum ConfigError {
+    InvalidSize,
+    TooLarge,
+    InvalidWinLength,
+}
+
+
+// The dimensions of the board and the number of tokens in a line needed to win.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct GameConfig {
+    width: usize,
+    height: usize,
+    win_length: usize,
+}
+
+
+impl Default for GameConfig {
+    // The standard 7x6 board with a win length of 4.
+    fn default() -> Self {
+        return GameConfig {
+            width: DEFAULT_BOARD_WIDTH,
+            height: DEFAULT_BOARD_HEIGHT,
+            win_length: DEFAULT_WIN_LENGTH,
+        };
+    }
+}
+
+
+impl GameConfig {
+    // Return a config for a board of the given size. The board must fit in a bitboard with room
+    // to spare for the shifts that find lines, so `(width + 1) * (height + 1)` can be at most
+    // 128. The win length must be at least 2 and must fit on the board in at least one
+    // direction.
+    pub fn new(width: usize, height: usize, win_length: usize) -> Result<Self, ConfigError> {
+        if width == 0 || height == 0 {
+            return Err(ConfigError::InvalidSize);
+        }
+        if (width + 1) * (height + 1) > Bitboard::BITS as usize {
+            return Err(ConfigError::TooLarge);
+        }
+        if win_length < 2 || win_length > max(width, height) {
+            return Err(ConfigError::InvalidWinLength);
+        }
+        return Ok(GameConfig { width, height, win_length });
+    }
+
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+
+    pub fn win_length(&self) -> usize {
+        return self.win_length;
+    }
+}
+
+
+// The reasons a move can be rejected.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum MoveError {
+    ColumnFull,
+    ColumnOutOfRange,
+    GameAlreadyOver,
+    NotYourTurn,
 }
 
 
 pub struct ConnectFourGame {
-    // The board is a vector of columns, with column 0 on the left.
-    // The rows within each column are from bottom-to-top (0 on the bottom).
-    pub board: Vec<Vec<i32>>,
+    position: Position,
+    state: GameState,
+    history: Vec<usize>,  // Every column played so far, in order.
+    redo_stack: Vec<usize>,  // Columns taken back by undo, most recent last.
+    draw_offer: Option<Player>,  // The player whose draw offer is standing, if any.
+}
+
+
+impl Default for ConnectFourGame {
+    fn default() -> Self {
+        return Self::new(GameConfig::default());
+    }
 }
 
 
 impl ConnectFourGame {
-    pub fn new() -> Self {
+    pub fn new(config: GameConfig) -> Self {
         return ConnectFourGame {
-            board: vec![Vec::new(); BOARD_WIDTH],
+            position: Position::new(config),
+            state: GameState::InProgress,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            draw_offer: None,
         }
     }
 
 
-    // Run the game until one player wins, a tie is reached, or the quit command is received.
-    pub fn play(&mut self) -> () {
-        let mut active_player: i32 = 1;  // Player 1 goes first.
-        loop {
-            printer::print_board(&self.board, active_player);
-            let turn_end_state: GameState = self.turn(active_player);
-            if turn_end_state == GameState::WinP1 || turn_end_state == GameState::WinP2 {
-                let winner_token: String = if turn_end_state == GameState::WinP1 {
-                        String::from(TOKEN_P1) } else { String::from(TOKEN_P2) };
-                printer::print_end(&self.board);  // Reprint the board when someone wins.
-                println!("{winner_token} wins!");
-                println!("Thanks for playing.");
-                return;
-            } else if turn_end_state == GameState::Tie {
-                printer::print_end(&self.board);  // Reprint the board if there's a tie.
-                println!("It's a tie!");
-                println!("Thanks for playing.");
-                return;
-            }
-            active_player = if active_player == 1 { 2 } else { 1 };
+    // Return the board dimensions and win length this game is played with.
+    pub fn config(&self) -> GameConfig {
+        return self.position.config();
+    }
+
+
+    // Return the bitboard position behind the game, for fast analysis.
+    pub fn position(&self) -> &Position {
+        return &self.position;
+    }
+
+
+    // Return the board as a vector of columns, with column 0 on the left.
+    // The rows within each column are from bottom-to-top (0 on the bottom).
+    pub fn board(&self) -> Vec<Vec<Player>> {
+        return self.position.columns();
+    }
+
+
+    // Return the contents of the given cell. Rows are counted from the bottom.
+    pub fn cell(&self, col: usize, row: usize) -> Cell {
+        return self.position.cell(col, row);
+    }
+
+
+    // Return the player whose turn it is. Player 1 goes first.
+    pub fn active_player(&self) -> Player {
+        return self.position.to_move();
+    }
+
+
+    // Return the current state of the game.
+    pub fn state(&self) -> GameState {
+        return self.state;
+    }
+
+
+    // Return the winning player, if any.
+    pub fn winner(&self) -> Option<Player> {
+        return match self.state {
+            GameState::Win(player) => Some(player),
+            GameState::OutOfTime(player) | GameState::Resigned(player) => Some(player.other()),
+            _ => None,
+        };
+    }
+
+
+    // Return whether the game has ended, whether or not it has a result.
+    pub fn is_over(&self) -> bool {
+        return self.state != GameState::InProgress;
+    }
+
+
+    // Play the given player's token in the given column (0-indexed), then pass the turn to
+    // the other player. Return the resulting game state, or the reason the move was rejected;
+    // a rejected move leaves the game unchanged.
+    pub fn play_move(&mut self, player: Player, col: usize) -> Result<GameState, MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        if player != self.active_player() {
+            return Err(MoveError::NotYourTurn);
+        }
+        if col >= self.config().width() {
+            return Err(MoveError::ColumnOutOfRange);
+        }
+        self.place(player, col)?;
+        self.state = self.check_win(player, col);
+        self.history.push(col);
+        self.redo_stack.clear();  // A new move starts a new line of play.
+        if self.draw_offer == Some(player.other()) {
+            self.draw_offer = None;  // Moving instead of accepting declines the offer.
+        }
+        return Ok(self.state);
+    }
+
+
+    // End the game with the player to move losing on time, and return the resulting state.
+    // A game that is already over is left unchanged.
+    pub fn lose_on_time(&mut self) -> GameState {
+        if !self.is_over() {
+            self.state = GameState::OutOfTime(self.active_player());
         }
+        return self.state;
     }
 
 
-    // Run one turn of the game. Return the resulting game state.
-    fn turn(&mut self, active_player: i32) -> GameState {
-        loop {
-            let input: String = self.get_player_input();
-            if input == COMMAND_QUIT {
-                return GameState::Tie;
-            }
-            let col: usize = self.convert_input_to_column(input);
-            if self.place(active_player, col) == TurnResult::Valid {
-                return self.check_win(active_player, col);
-            }
+    // End the game with the given player resigning, and return the resulting state. A game
+    // that is already over is left unchanged.
+    pub fn resign(&mut self, player: Player) -> GameState {
+        if !self.is_over() {
+            self.state = GameState::Resigned(player);
         }
-        
-    }
-
-
-    // Loop until a valid player input is received.
-    fn get_player_input(&self) -> String {
-        loop {
-            let mut input = String::new();
-            let _b = std::io::stdin().read_line(&mut input).unwrap();
-            if input.len() == 2 {
-                input.pop();  // Truncate the trailing newline char.
-            }
-            if self.is_valid_input(&input) {
-                return input;
-            }
-            println!("Invalid move. Please try again.");
+        return self.state;
+    }
+
+
+    // Offer a draw on behalf of the given player. The offer stands until the other player
+    // accepts it or makes a move instead.
+    pub fn offer_draw(&mut self, player: Player) {
+        if !self.is_over() {
+            self.draw_offer = Some(player);
         }
     }
 
 
-    // Return whether the given input is a valid game control.
-    fn is_valid_input(&self, input: &String) -> bool {
-        return input.len() == 1 && (input == COMMAND_QUIT || (
-                '1' <= input.chars().nth(0).unwrap() && 
-                input.chars().nth(0).unwrap() <= '7'));
+    // Return the player whose draw offer is standing, if any.
+    pub fn draw_offer(&self) -> Option<Player> {
+        return self.draw_offer;
     }
 
 
-    // Convert the given column input (1-indexed, string) into a column index (0-indexed, usize).
-    fn convert_input_to_column(&self, input: String) -> usize {
-        let col: usize = input.parse::<usize>().unwrap() - 1;
-        return col;
+    // Accept the other player's standing draw offer on behalf of the given player, ending the
+    // game. Return whether there was an offer to accept.
+    pub fn accept_draw(&mut self, player: Player) -> bool {
+        if self.is_over() || self.draw_offer != Some(player.other()) {
+            return false;
+        }
+        self.state = GameState::DrawAgreed;
+        self.draw_offer = None;
+        return true;
     }
 
 
-    // Return whether a given column is full.
-    fn is_column_full(&self, col: usize) -> bool {
-        return self.board[col].len() >= BOARD_HEIGHT;
+    // Abandon the game with no result, and return the resulting state. A game that is already
+    // over is left unchanged.
+    pub fn abort(&mut self) -> GameState {
+        if !self.is_over() {
+            self.state = GameState::Aborted;
+        }
+        return self.state;
     }
 
 
-    // Return whether the board is full.
-    fn is_board_full(&self) -> bool {
-        for col in &self.board {
-            if col.len() != BOARD_HEIGHT {
-                return false;
-            }
+    // Return the (col, row) coordinates of the cells that formed the winning line, ordered by
+    // column then row. A move can complete several lines at once, in which case all of their
+    // cells are returned. The result is empty unless the game has been won.
+    pub fn winning_line(&self) -> Vec<(usize, usize)> {
+        return match (self.state, self.history.last()) {
+            (GameState::Win(player), Some(col)) => self.check_winning_cells(player, *col),
+            _ => Vec::new(),
+        };
+    }
+
+
+    // Return the columns where the player to move would win immediately, in ascending order.
+    // The result is empty once the game is over.
+    pub fn winning_columns(&self) -> Vec<usize> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        return self.position.winning_columns(self.active_player());
+    }
+
+
+    // Return the columns where the other player would win on their next turn, in ascending
+    // order. The player to move must block these, and can't block more than one. The result
+    // is empty once the game is over.
+    pub fn must_block_columns(&self) -> Vec<usize> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        return self.position.winning_columns(self.active_player().other());
+    }
+
+
+    // Return the (col, row) coordinates of the empty cells that would complete a line for the
+    // given player, ordered by column then row. Cells that can't be played yet are included,
+    // since they shape the rest of the game.
+    pub fn threat_cells(&self, player: Player) -> Vec<(usize, usize)> {
+        return self.position.coordinates(self.position.threat_cells(player));
+    }
+
+
+    // Return the columns played so far, in order.
+    pub fn history(&self) -> &[usize] {
+        return &self.history;
+    }
+
+
+    // Return whether there is a move to take back.
+    pub fn can_undo(&self) -> bool {
+        return !self.history.is_empty();
+    }
+
+
+    // Return whether there is a taken-back move to replay.
+    pub fn can_redo(&self) -> bool {
+        return !self.redo_stack.is_empty();
+    }
+
+
+    // Return the moves taken back by undo that can still be redone, next one first.
+    pub fn redo_moves(&self) -> Vec<usize> {
+        return self.redo_stack.iter().rev().copied().collect();
+    }
+
+
+    // Take back the last move, passing the turn back to the player who made it.
+    // Return whether there was a move to take back. A game that ended away from the board, by
+    // resigning, agreeing to a draw, aborting or losing on time, can't be taken back.
+    pub fn undo(&mut self) -> bool {
+        if self.is_final() {
+            return false;
+        }
+        let col: usize = match self.history.pop() {
+            Some(col) => col,
+            None => return false,
+        };
+        self.position.undo(col);
+        self.state = GameState::InProgress;  // Every earlier position was still in progress.
+        self.draw_offer = None;
+        self.redo_stack.push(col);
+        return true;
+    }
+
+
+    // Replay the last move taken back by undo.
+    // Return whether there was a move to replay.
+    pub fn redo(&mut self) -> bool {
+        if self.is_final() {
+            return false;
         }
+        let col: usize = match self.redo_stack.pop() {
+            Some(col) => col,
+            None => return false,
+        };
+        let player: Player = self.active_player();
+        self.position.place(player, col);
+        self.state = self.check_win(player, col);
+        self.history.push(col);
         return true;
     }
 
 
+    // Return whether the game ended away from the board, so that undo and redo can't revive it.
+    fn is_final(&self) -> bool {
+        return matches!(self.state, GameState::OutOfTime(_) | GameState::Resigned(_)
+                                    | GameState::DrawAgreed | GameState::Aborted);
+    }
+
+
+    // Return whether a given column is full.
+    pub fn is_column_full(&self, col: usize) -> bool {
+        return self.position.column_height(col) >= self.config().height();
+    }
+
+
+    // Return whether the board is full.
+    pub fn is_board_full(&self) -> bool {
+        return self.position.is_full();
+    }
+
+
     // Place the given player's token in the given column.
-    // Return whether the move is valid.
-    fn place(&mut self, player: i32, col: usize) -> TurnResult {
+    // Return an error if the column is full.
+    fn place(&mut self, player: Player, col: usize) -> Result<(), MoveError> {
         if self.is_column_full(col) {
-            println!("Column {} is full. Try another column.", col + 1);
-            return TurnResult::Invalid;
+            return Err(MoveError::ColumnFull);
         }
-        self.board[col].push(player);
-        return TurnResult::Valid;
+        self.position.place(player, col);
+        return Ok(());
     }
 
 
@@ -149,16 +437,9 @@ impl ConnectFourGame {
     // Return a tie if the board is full and there is no winner.
     // Since moves don't affect the arrangement of already-placed tokens, we only need to
     // check if the newest token results in a win.
-    fn check_win(&self, player_hint: i32, col_hint: usize) -> GameState {
-        let states: Vec<GameState> = vec![
-                self.check_row_win(player_hint, col_hint),
-                self.check_column_win(player_hint, col_hint),
-                self.check_forward_diagonal_win(player_hint, col_hint),
-                self.check_back_diagonal_win(player_hint, col_hint)];
-        for state in states {
-            if state != GameState::InProgress {
-                return state;
-            }
+    fn check_win(&self, player_hint: Player, col_hint: usize) -> GameState {
+        if !self.check_winning_cells(player_hint, col_hint).is_empty() {
+            return GameState::Win(player_hint);
         }
         if self.is_board_full() {
             return GameState::Tie;
@@ -167,118 +448,58 @@ impl ConnectFourGame {
     }
 
 
+    // Return the (col, row) coordinates of every cell in a winning line through the topmost
+    // piece in the given column, in all directions. The result is empty if there is no win.
+    fn check_winning_cells(&self, player_hint: Player, col_hint: usize) -> Vec<(usize, usize)> {
+        let mut cells: Vec<(usize, usize)> = Vec::new();
+        cells.extend(self.check_row_win(player_hint, col_hint));
+        cells.extend(self.check_column_win(player_hint, col_hint));
+        cells.extend(self.check_forward_diagonal_win(player_hint, col_hint));
+        cells.extend(self.check_back_diagonal_win(player_hint, col_hint));
+        cells.sort();
+        cells.dedup();  // Lines in different directions share the topmost piece.
+        return cells;
+    }
+
+
     // Check if a given column contains a win for the given player.
-    fn check_column_win(&self, player_hint: i32, col_hint: usize) -> GameState {
-        let mut consecutive: usize = 0;
-        for i in (0..self.board[col_hint].len()).rev() {
-            if self.board[col_hint][i] == player_hint {
-                consecutive += 1;
-            } else {
-                consecutive = 0;
-            }
-            if consecutive >= WIN_LENGTH {
-                return match player_hint {
-                    1 => GameState::WinP1,
-                    2 => GameState::WinP2,
-                    _ => GameState::Tie,
-                };
-            }
-        }
-        return GameState::InProgress;
+    // Return the cells of the winning line, or nothing if there is no win.
+    fn check_column_win(&self, player_hint: Player, col_hint: usize) -> Vec<(usize, usize)> {
+        return self.check_line_win(player_hint, col_hint, Direction::Vertical);
     }
 
-    
+
     // Check if a win has occurred in the row of the topmost piece in the given column.
-    fn check_row_win(&self, player_hint: i32, col_hint: usize) -> GameState {
-        let row: usize = self.board[col_hint].len() - 1;
-        let mut consecutive: usize = 0;
-        for col in 0..BOARD_WIDTH {
-            if self.board[col].len() <= row {
-                consecutive = 0;
-                continue;
-            }
-            if self.board[col][row] == player_hint {
-                consecutive += 1;
-            } else {
-                consecutive = 0;
-            }
-            if consecutive >= WIN_LENGTH {
-                return match player_hint {
-                    1 => GameState::WinP1,
-                    2 => GameState::WinP2,
-                    _ => GameState::Tie,
-                };
-            }
-        }
-        return GameState::InProgress;
+    // Return the cells of the winning line, or nothing if there is no win.
+    fn check_row_win(&self, player_hint: Player, col_hint: usize) -> Vec<(usize, usize)> {
+        return self.check_line_win(player_hint, col_hint, Direction::Horizontal);
     }
 
-    
+
     // Check if a win has occurred on the back-diagonal containing the topmost piece in the
     // given column. A back-diagonal is shaped like '\'.
-    fn check_back_diagonal_win(&self, player_hint: i32, col_hint: usize) -> GameState {
-        let placed_row: usize = self.board[col_hint].len() - 1;
-        let sum = placed_row + col_hint;
-        let mut row: usize = max(sum as i32 - (BOARD_WIDTH - 1) as i32, 0) as usize;
-        let mut col: i32 = min(sum, BOARD_WIDTH - 1) as i32;
-        let mut consecutive: usize = 0;
-        while col >= 0 && row < BOARD_WIDTH {
-            if self.board[col as usize].len() <= row {
-                consecutive = 0;
-                row += 1;
-                col -= 1;
-                continue;
-            }
-            if self.board[col as usize][row] == player_hint {
-                consecutive += 1;
-            } else {
-                consecutive = 0;
-            }
-            if consecutive >= WIN_LENGTH {
-                return match player_hint {
-                    1 => GameState::WinP1,
-                    2 => GameState::WinP2,
-                    _ => GameState::Tie,
-                };
-            }
-            row += 1;
-            col -= 1;
-        }
-        return GameState::InProgress;
+    // Return the cells of the winning line, or nothing if there is no win.
+    fn check_back_diagonal_win(&self, player_hint: Player, col_hint: usize)
+            -> Vec<(usize, usize)> {
+        return self.check_line_win(player_hint, col_hint, Direction::BackDiagonal);
     }
 
 
     // Check if a win has occurred on the forward-diagonal containing the topmost piece in the
     // given column. A forward-diagonal is shaped like '/'.
-    fn check_forward_diagonal_win(&self, player_hint: i32, col_hint: usize) -> GameState {
-        let placed_row: usize = self.board[col_hint].len() - 1;
-        let diff: usize = col_hint.abs_diff(placed_row);
-        let mut row: usize = if placed_row > col_hint { diff } else { 0 };
-        let mut col: usize = if placed_row < col_hint { diff } else { 0 };
-        let mut consecutive: usize = 0;
-        while row < BOARD_HEIGHT && col < BOARD_WIDTH {
-            if self.board[col].len() <= row {
-                consecutive = 0;
-                row += 1;
-                col += 1;
-                continue;
-            }
-            if self.board[col][row] == player_hint {
-                consecutive += 1;
-            } else {
-                consecutive = 0;
-            }
-            if consecutive >= WIN_LENGTH {
-                return match player_hint {
-                    1 => GameState::WinP1,
-                    2 => GameState::WinP2,
-                    _ => GameState::Tie,
-                };
-            }
-            row += 1;
-            col += 1;
-        }
-        return GameState::InProgress;
+    // Return the cells of the winning line, or nothing if there is no win.
+    fn check_forward_diagonal_win(&self, player_hint: Player, col_hint: usize)
+            -> Vec<(usize, usize)> {
+        return self.check_line_win(player_hint, col_hint, Direction::ForwardDiagonal);
+    }
+
+
+    // Return the cells of every line of the winning length for the given player in the given
+    // direction that passes through the topmost piece in the given column.
+    fn check_line_win(&self, player_hint: Player, col_hint: usize, direction: Direction)
+            -> Vec<(usize, usize)> {
+        let top: Bitboard = self.position.top_cell_bit(col_hint);
+        return self.position.coordinates(self.position.lines_through(player_hint, top, direction));
     }
 }
 
@@ -287,91 +508,110 @@ impl ConnectFourGame {
 mod tests {
     use super::*;
 
+    // Replace the game's board with the given columns of player numbers (1 or 2).
+    fn set_board(game: &mut ConnectFourGame, board: Vec<Vec<i32>>) {
+        let columns: Vec<Vec<Player>> = board.iter()
+                .map(|column| column.iter()
+                        .map(|player| if *player == 1 { Player::One } else { Player::Two })
+                        .collect())
+                .collect();
+        game.position = Position::from_columns(game.config(), &columns);
+    }
+
+    // Play the given columns in order, alternating players.
+    fn play(game: &mut ConnectFourGame, cols: &[usize]) {
+        for col in cols {
+            game.play_move(game.active_player(), *col).unwrap();
+        }
+    }
+
     #[test]
     fn test_new_board_is_empty() {
-        let game = ConnectFourGame::new();
-        for column in game.board {
+        let game = ConnectFourGame::new(GameConfig::default());
+        for column in game.board() {
             assert_eq!(column.len(), 0);
         }
     }
 
     #[test]
     fn test_place_success() {
-        let mut game = ConnectFourGame::new();
-        assert_eq!(game.place(1, 0), TurnResult::Valid);
-        assert_eq!(game.board[0].len(), 1);
-        assert_eq!(game.board[0][0], 1);
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        assert_eq!(game.place(Player::One, 0), Ok(()));
+        assert_eq!(game.board()[0].len(), 1);
+        assert_eq!(game.board()[0][0], Player::One);
     }
 
     #[test]
     fn test_invalid_place() {
-        let mut game = ConnectFourGame::new();
-        for _i in 0..BOARD_HEIGHT {
-            assert_eq!(game.place(1, 0), TurnResult::Valid);
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        for _i in 0..DEFAULT_BOARD_HEIGHT {
+            assert_eq!(game.place(Player::One, 0), Ok(()));
         }
-        assert_eq!(game.place(1, 0), TurnResult::Invalid);
+        assert_eq!(game.place(Player::One, 0), Err(MoveError::ColumnFull));
     }
 
     #[test]
     fn test_column_not_full() {
-        let game = ConnectFourGame::new();
+        let game = ConnectFourGame::new(GameConfig::default());
         assert!(!game.is_column_full(0));
     }
 
     #[test]
     fn test_column_full() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![vec![1; BOARD_HEIGHT]; BOARD_WIDTH];
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![vec![1; DEFAULT_BOARD_HEIGHT]; DEFAULT_BOARD_WIDTH]);
         assert!(game.is_column_full(0));
     }
 
     #[test]
     fn test_board_not_full() {
-        let game = ConnectFourGame::new();
+        let game = ConnectFourGame::new(GameConfig::default());
         assert!(!game.is_board_full());
     }
 
     #[test]
     fn test_board_full() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![vec![1; BOARD_HEIGHT]; BOARD_WIDTH];
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![vec![1; DEFAULT_BOARD_HEIGHT]; DEFAULT_BOARD_WIDTH]);
         assert!(game.is_board_full());
     }
 
     #[test]
     fn test_column_win_success() {
-        let mut game = ConnectFourGame::new();
-        game.board[0] = vec![2,1,1,1,1];
-        assert_eq!(game.check_column_win(/*player_hint=*/1, /*col_hint=*/0), GameState::WinP1);
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![vec![2,1,1,1,1]]);
+        assert_eq!(game.check_column_win(/*player_hint=*/Player::One, /*col_hint=*/0),
+                   vec![(0, 1), (0, 2), (0, 3), (0, 4)]);
     }
 
     #[test]
     fn test_no_column_win() {
-        let mut game = ConnectFourGame::new();
-        game.board[0] = vec![2,1,2,1];
-        assert_eq!(game.check_column_win(/*player_hint=*/1, /*col_hint=*/0), GameState::InProgress);
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![vec![2,1,2,1]]);
+        assert!(game.check_column_win(/*player_hint=*/Player::One, /*col_hint=*/0).is_empty());
     }
 
     #[test]
     fn test_row_win_with_blanks() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![vec![1]; BOARD_WIDTH];
-        game.board[0] = vec![];
-        game.board[2] = vec![];
-        assert_eq!(game.check_row_win(/*player_hint=*/1, /*col_hint=*/4), GameState::WinP1);
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        let mut board: Vec<Vec<i32>> = vec![vec![1]; DEFAULT_BOARD_WIDTH];
+        board[0] = vec![];
+        board[2] = vec![];
+        set_board(&mut game, board);
+        assert!(!game.check_row_win(/*player_hint=*/Player::One, /*col_hint=*/4).is_empty());
     }
 
     #[test]
     fn test_row_win() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![vec![1]; BOARD_WIDTH];
-        assert_eq!(game.check_row_win(/*player_hint=*/1, /*col_hint=*/0), GameState::WinP1);
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![vec![1]; DEFAULT_BOARD_WIDTH]);
+        assert!(!game.check_row_win(/*player_hint=*/Player::One, /*col_hint=*/0).is_empty());
     }
 
     #[test]
     fn test_no_row_win_with_blanks() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -379,14 +619,14 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![2], 
-        ];
-        assert_eq!(game.check_row_win(/*player_hint=*/1, /*col_hint=*/3), GameState::InProgress);
+        ]);
+        assert!(game.check_row_win(/*player_hint=*/Player::One, /*col_hint=*/3).is_empty());
     }
 
     #[test]
     fn test_no_row_win() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -394,14 +634,14 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![2], 
-        ];
-        assert_eq!(game.check_row_win(/*player_hint=*/2, /*col_hint=*/6), GameState::InProgress);
+        ]);
+        assert!(game.check_row_win(/*player_hint=*/Player::Two, /*col_hint=*/6).is_empty());
     }
 
     #[test]
     fn test_forward_diagonal_win() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![2,2], 
             vec![2], 
@@ -409,15 +649,15 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![2,1,2,1,1], 
-        ];
-        assert_eq!(game.check_forward_diagonal_win(/*player_hint=*/1, /*col_hint=*/3),
-                   GameState::WinP1);
+        ]);
+        assert!(!game.check_forward_diagonal_win(/*player_hint=*/Player::One, /*col_hint=*/3)
+                .is_empty());
     }
 
     #[test]
     fn test_forward_diagonal_win_high() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1,2], 
             vec![2,2,2], 
             vec![2,1,2,2], 
@@ -425,15 +665,15 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,], 
             vec![2,1,2,1,1], 
-        ];
-        assert_eq!(game.check_forward_diagonal_win(/*player_hint=*/2, /*col_hint=*/3),
-                   GameState::WinP2);
+        ]);
+        assert!(!game.check_forward_diagonal_win(/*player_hint=*/Player::Two, /*col_hint=*/3)
+                .is_empty());
     }
 
     #[test]
     fn test_forward_diagonal_win_long() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![2,2], 
             vec![2,1,2], 
@@ -441,15 +681,15 @@ mod tests {
             vec![1,2,2,1,2], 
             vec![1,1,2,1,2,2], 
             vec![2,1,2,1,1], 
-        ];
-        assert_eq!(game.check_forward_diagonal_win(/*player_hint=*/2, /*col_hint=*/3),
-                   GameState::WinP2);
+        ]);
+        assert!(!game.check_forward_diagonal_win(/*player_hint=*/Player::Two, /*col_hint=*/3)
+                .is_empty());
     }
 
     #[test]
     fn test_no_forward_diagonal_win() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -457,15 +697,15 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![2], 
-        ];
-        assert_eq!(game.check_forward_diagonal_win(/*player_hint=*/1, /*col_hint=*/3),
-                   GameState::InProgress);
+        ]);
+        assert!(game.check_forward_diagonal_win(/*player_hint=*/Player::One, /*col_hint=*/3)
+                .is_empty());
     }
 
     #[test]
     fn test_back_diagonal_win() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![2,2], 
             vec![2], 
@@ -473,15 +713,15 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![1,1,2,], 
-        ];
-        assert_eq!(game.check_back_diagonal_win(/*player_hint=*/1, /*col_hint=*/3),
-                   GameState::WinP1);
+        ]);
+        assert!(!game.check_back_diagonal_win(/*player_hint=*/Player::One, /*col_hint=*/3)
+                .is_empty());
     }
 
     #[test]
     fn test_back_diagonal_win_high() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![2,2], 
             vec![2], 
@@ -489,15 +729,15 @@ mod tests {
             vec![2,1,1,1,2], 
             vec![1,2,1,2,1], 
             vec![2,1,2,2,], 
-        ];
-        assert_eq!(game.check_back_diagonal_win(/*player_hint=*/1, /*col_hint=*/3),
-                   GameState::WinP1);
+        ]);
+        assert!(!game.check_back_diagonal_win(/*player_hint=*/Player::One, /*col_hint=*/3)
+                .is_empty());
     }
 
     #[test]
     fn test_back_diagonal_win_long() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![2,2], 
             vec![2,1,2,1,2], 
@@ -505,15 +745,15 @@ mod tests {
             vec![1,2,2,1,1], 
             vec![1,2,2,1,2,2], 
             vec![2,1,2,1,1], 
-        ];
-        assert_eq!(game.check_back_diagonal_win(/*player_hint=*/2, /*col_hint=*/2),
-                   GameState::WinP2);
+        ]);
+        assert!(!game.check_back_diagonal_win(/*player_hint=*/Player::Two, /*col_hint=*/2)
+                .is_empty());
     }
 
     #[test]
     fn test_no_back_diagonal_win() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -521,15 +761,15 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![2], 
-        ];
-        assert_eq!(game.check_back_diagonal_win(/*player_hint=*/2, /*col_hint=*/1),
-                   GameState::InProgress);
+        ]);
+        assert!(game.check_back_diagonal_win(/*player_hint=*/Player::Two, /*col_hint=*/1)
+                .is_empty());
     }
 
     #[test]
     fn test_check_win_row() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -537,14 +777,15 @@ mod tests {
             vec![1,1,1,2], 
             vec![1,1,2,1], 
             vec![2,1], 
-        ];
-        assert_eq!(game.check_win(/*player_hint=*/1, /*col_hint=*/3), GameState::WinP1);
+        ]);
+        assert_eq!(game.check_win(/*player_hint=*/Player::One, /*col_hint=*/3),
+                   GameState::Win(Player::One));
     }
 
     #[test]
     fn test_check_win_column() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -552,14 +793,15 @@ mod tests {
             vec![2,2,2,2], 
             vec![1,1,2,1], 
             vec![2], 
-        ];
-        assert_eq!(game.check_win(/*player_hint=*/2, /*col_hint=*/4), GameState::WinP2);
+        ]);
+        assert_eq!(game.check_win(/*player_hint=*/Player::Two, /*col_hint=*/4),
+                   GameState::Win(Player::Two));
     }
 
     #[test]
     fn test_check_win_forward_diagonal() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![1], 
@@ -567,14 +809,15 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![2], 
-        ];
-        assert_eq!(game.check_win(/*player_hint=*/1, /*col_hint=*/3), GameState::WinP1);
+        ]);
+        assert_eq!(game.check_win(/*player_hint=*/Player::One, /*col_hint=*/3),
+                   GameState::Win(Player::One));
     }
 
     #[test]
     fn test_check_win_back_diagonal() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -582,14 +825,15 @@ mod tests {
             vec![1,2,1,2], 
             vec![1,1,2,1], 
             vec![1], 
-        ];
-        assert_eq!(game.check_win(/*player_hint=*/1, /*col_hint=*/3), GameState::WinP1);
+        ]);
+        assert_eq!(game.check_win(/*player_hint=*/Player::One, /*col_hint=*/3),
+                   GameState::Win(Player::One));
     }
 
     #[test]
     fn test_check_win_no_win() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1], 
             vec![1,2], 
             vec![], 
@@ -597,14 +841,15 @@ mod tests {
             vec![1,2], 
             vec![1,1,2,1], 
             vec![2], 
-        ];
-        assert_eq!(game.check_win(/*player_hint=*/2, /*col_hint=*/4), GameState::InProgress);
+        ]);
+        assert_eq!(game.check_win(/*player_hint=*/Player::Two, /*col_hint=*/4),
+                   GameState::InProgress);
     }
 
     #[test]
     fn test_check_win_tie() {
-        let mut game = ConnectFourGame::new();
-        game.board = vec![
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
             vec![1,2,1,2,1,2], 
             vec![2,1,2,1,2,1], 
             vec![2,1,2,1,2,1], 
@@ -612,34 +857,325 @@ mod tests {
             vec![2,1,2,1,2,1], 
             vec![2,1,2,1,2,1], 
             vec![1,2,1,2,1,2], 
-        ];
-        assert_eq!(game.check_win(/*player_hint=*/2, /*col_hint=*/0), GameState::Tie);
+        ]);
+        assert_eq!(game.check_win(/*player_hint=*/Player::Two, /*col_hint=*/0), GameState::Tie);
+    }
+
+    #[test]
+    fn test_play_move_alternates_players() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        assert_eq!(game.active_player(), Player::One);
+        assert_eq!(game.play_move(Player::One, 3), Ok(GameState::InProgress));
+        assert_eq!(game.active_player(), Player::Two);
+        assert_eq!(game.play_move(Player::Two, 3), Ok(GameState::InProgress));
+        assert_eq!(game.board()[3], vec![Player::One, Player::Two]);
+        assert_eq!(game.active_player(), Player::One);
+    }
+
+    #[test]
+    fn test_play_move_out_of_range() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        assert_eq!(game.play_move(Player::One, DEFAULT_BOARD_WIDTH),
+                   Err(MoveError::ColumnOutOfRange));
+        assert_eq!(game.active_player(), Player::One);
+    }
+
+    #[test]
+    fn test_play_move_column_full() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 0, 0, 0, 0, 0]);
+        assert_eq!(game.play_move(Player::One, 0), Err(MoveError::ColumnFull));
+        assert_eq!(game.history().len(), DEFAULT_BOARD_HEIGHT);
+    }
+
+    #[test]
+    fn test_play_move_not_your_turn() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        assert_eq!(game.play_move(Player::Two, 0), Err(MoveError::NotYourTurn));
+        assert_eq!(game.board()[0], Vec::<Player>::new());
+    }
+
+    #[test]
+    fn test_play_move_win_ends_game() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 1, 0, 1, 0, 1]);
+        assert_eq!(game.play_move(Player::One, 0), Ok(GameState::Win(Player::One)));
+        assert_eq!(game.state(), GameState::Win(Player::One));
+        assert_eq!(game.winner(), Some(Player::One));
+        assert_eq!(game.play_move(Player::Two, 2), Err(MoveError::GameAlreadyOver));
+    }
+
+    #[test]
+    fn test_config_rejects_empty_board() {
+        assert_eq!(GameConfig::new(0, 6, 4), Err(ConfigError::InvalidSize));
+    }
+
+    #[test]
+    fn test_config_rejects_unreachable_win_length() {
+        assert_eq!(GameConfig::new(4, 4, 5), Err(ConfigError::InvalidWinLength));
+        assert_eq!(GameConfig::new(7, 6, 1), Err(ConfigError::InvalidWinLength));
+    }
+
+    #[test]
+    fn test_wide_board_row_win() {
+        let mut game = ConnectFourGame::new(GameConfig::new(10, 8, 4).unwrap());
+        let mut board: Vec<Vec<i32>> = vec![Vec::new(); 10];
+        board[6] = vec![1];
+        board[7] = vec![1];
+        board[8] = vec![1];
+        board[9] = vec![1];
+        set_board(&mut game, board);
+        assert!(!game.check_row_win(/*player_hint=*/Player::One, /*col_hint=*/9).is_empty());
+    }
+
+    #[test]
+    fn test_connect_five_needs_five() {
+        let mut game = ConnectFourGame::new(GameConfig::new(9, 7, 5).unwrap());
+        set_board(&mut game, vec![vec![2,2,2,2]]);
+        assert!(game.check_column_win(/*player_hint=*/Player::Two, /*col_hint=*/0).is_empty());
+        set_board(&mut game, vec![vec![2,2,2,2,2]]);
+        assert!(!game.check_column_win(/*player_hint=*/Player::Two, /*col_hint=*/0).is_empty());
+    }
+
+    #[test]
+    fn test_back_diagonal_win_tall_board() {
+        let mut game = ConnectFourGame::new(GameConfig::new(4, 8, 4).unwrap());
+        set_board(&mut game, vec![
+            vec![2,1,2,1,2,1,2,1],
+            vec![1,2,1,2,1,2,1],
+            vec![2,1,2,1,2,1],
+            vec![1,2,1,2,1],
+        ]);
+        assert!(!game.check_back_diagonal_win(/*player_hint=*/Player::One, /*col_hint=*/3)
+                .is_empty());
+    }
+
+    #[test]
+    fn test_config_rejects_board_too_large_for_bitboard() {
+        assert_eq!(GameConfig::new(16, 8, 4), Err(ConfigError::TooLarge));
+        assert!(GameConfig::new(10, 8, 4).is_ok());
+        // One column still needs room for the shift along a row or a diagonal.
+        assert_eq!(GameConfig::new(1, 127, 4), Err(ConfigError::TooLarge));
+        let mut game = ConnectFourGame::new(GameConfig::new(1, 63, 4).unwrap());
+        assert_eq!(game.play_move(Player::One, 0), Ok(GameState::InProgress));
+    }
+
+    #[test]
+    fn test_row_win_must_include_hint() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![vec![1], vec![1], vec![1], vec![1], vec![], vec![2], vec![2]]);
+        assert!(!game.check_row_win(/*player_hint=*/Player::One, /*col_hint=*/0).is_empty());
+        assert!(game.check_row_win(/*player_hint=*/Player::Two, /*col_hint=*/6).is_empty());
+    }
+
+    #[test]
+    fn test_history_records_moves() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[3, 4]);
+        assert_eq!(game.play_move(Player::One, 9), Err(MoveError::ColumnOutOfRange));
+        assert_eq!(game.history(), &[3, 4]);
     }
 
     #[test]
-    fn test_accept_valid_input() {
-        let game = ConnectFourGame::new();
-        assert!(game.is_valid_input(&String::from("2")));
+    fn test_undo_restores_previous_position() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[3, 4]);
+        assert!(game.undo());
+        assert_eq!(game.history(), &[3]);
+        assert_eq!(game.board()[4], Vec::<Player>::new());
+        assert_eq!(game.active_player(), Player::Two);
+        assert!(game.undo());
+        assert!(!game.undo());
+        assert_eq!(game.active_player(), Player::One);
     }
 
     #[test]
-    fn test_accept_valid_input_quit() {
-        let game = ConnectFourGame::new();
-        assert!(game.is_valid_input(&String::from("q")));
+    fn test_undo_win_resumes_game() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 1, 0, 1, 0, 1, 0]);
+        assert!(game.is_over());
+        assert!(game.undo());
+        assert_eq!(game.state(), GameState::InProgress);
+        assert_eq!(game.play_move(Player::One, 2), Ok(GameState::InProgress));
     }
 
     #[test]
-    fn test_reject_invalid_input() {
-        let game = ConnectFourGame::new();
-        assert!(!game.is_valid_input(&String::from("x")));
+    fn test_lose_on_time() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[3]);
+        assert_eq!(game.lose_on_time(), GameState::OutOfTime(Player::Two));
+        assert_eq!(game.winner(), Some(Player::One));
+        assert_eq!(game.play_move(Player::Two, 3), Err(MoveError::GameAlreadyOver));
+        assert!(!game.undo());
+        assert!(!game.redo());
+        assert_eq!(game.state(), GameState::OutOfTime(Player::Two));
     }
 
     #[test]
-    fn test_convert_input_to_column() {
-        let game = ConnectFourGame::new();
-        for x in 1..7 {
-            assert_eq!(game.convert_input_to_column(x.to_string()), x - 1);
+    fn test_no_takebacks_after_game_ended_off_board() {
+        type End = fn(&mut ConnectFourGame) -> GameState;
+        let endings: [(End, GameState); 4] = [
+            (|game| game.resign(Player::One), GameState::Resigned(Player::One)),
+            (|game| game.abort(), GameState::Aborted),
+            (|game| {
+                game.offer_draw(Player::One);
+                game.accept_draw(Player::Two);
+                return game.state();
+            }, GameState::DrawAgreed),
+            (|game| game.lose_on_time(), GameState::OutOfTime(Player::Two)),
+        ];
+        for (end, state) in endings {
+            let mut game = ConnectFourGame::new(GameConfig::default());
+            play(&mut game, &[3, 4]);
+            assert!(game.undo());
+            assert_eq!(end(&mut game), state);
+            assert!(!game.redo());
+            assert!(!game.undo());
+            assert_eq!(game.state(), state);
+            assert_eq!(game.history(), &[3]);
         }
     }
-}
 
+    #[test]
+    fn test_resign() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        assert_eq!(game.resign(Player::One), GameState::Resigned(Player::One));
+        assert_eq!(game.winner(), Some(Player::Two));
+        assert_eq!(game.abort(), GameState::Resigned(Player::One));
+    }
+
+    #[test]
+    fn test_draw_by_agreement() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        game.offer_draw(Player::One);
+        assert!(!game.accept_draw(Player::One));
+        play(&mut game, &[3]);
+        assert_eq!(game.draw_offer(), Some(Player::One));
+        assert!(game.accept_draw(Player::Two));
+        assert_eq!(game.state(), GameState::DrawAgreed);
+        assert_eq!(game.winner(), None);
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn test_moving_declines_draw() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        game.offer_draw(Player::One);
+        play(&mut game, &[3, 3]);
+        assert_eq!(game.draw_offer(), None);
+        assert!(!game.accept_draw(Player::One));
+    }
+
+    #[test]
+    fn test_abort() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[3]);
+        assert_eq!(game.abort(), GameState::Aborted);
+        assert_eq!(game.winner(), None);
+        assert_eq!(game.play_move(Player::Two, 3), Err(MoveError::GameAlreadyOver));
+    }
+
+    #[test]
+    fn test_redo_replays_undone_moves() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 1, 0, 1, 0, 1, 0]);
+        game.undo();
+        game.undo();
+        assert_eq!(game.redo_moves(), vec![1, 0]);
+        assert!(game.redo());
+        assert!(game.redo());
+        assert!(!game.redo());
+        assert_eq!(game.history(), &[0, 1, 0, 1, 0, 1, 0]);
+        assert_eq!(game.state(), GameState::Win(Player::One));
+    }
+
+    #[test]
+    fn test_new_move_clears_redo() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[3]);
+        game.undo();
+        assert!(game.can_redo());
+        play(&mut game, &[2]);
+        assert!(!game.can_redo());
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn test_cell_contents() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[2]);
+        assert_eq!(game.cell(2, 0), Cell::Occupied(Player::One));
+        assert_eq!(game.cell(2, 1), Cell::Empty);
+    }
+
+    #[test]
+    fn test_other_player() {
+        assert_eq!(Player::One.other(), Player::Two);
+        assert_eq!(Player::Two.other(), Player::One);
+    }
+
+    #[test]
+    fn test_winning_line_empty_without_win() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 1, 0]);
+        assert!(game.winning_line().is_empty());
+    }
+
+    #[test]
+    fn test_winning_line_column() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 1, 0, 1, 0, 1, 0]);
+        assert_eq!(game.winning_line(), vec![(0, 0), (0, 1), (0, 2), (0, 3)]);
+    }
+
+    #[test]
+    fn test_winning_line_longer_than_win_length() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 0, 1, 1, 3, 3, 4, 4, 2]);
+        assert_eq!(game.winning_line(), vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn test_check_winning_cells_two_lines() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        set_board(&mut game, vec![
+            vec![1,2,2,2],
+            vec![2,1,2],
+            vec![2,2,1],
+            vec![1,1,1,1],
+            vec![],
+            vec![],
+            vec![],
+        ]);
+        assert_eq!(game.check_winning_cells(/*player_hint=*/Player::One, /*col_hint=*/3),
+                   vec![(0, 0), (1, 1), (2, 2), (3, 0), (3, 1), (3, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn test_winning_and_must_block_columns() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 6, 1, 6, 2]);
+        // Player 1 has three along the bottom and threatens to complete it in column 3.
+        assert!(game.winning_columns().is_empty());
+        assert_eq!(game.must_block_columns(), vec![3]);
+        play(&mut game, &[6]);
+        assert_eq!(game.winning_columns(), vec![3]);
+        assert_eq!(game.must_block_columns(), vec![6]);
+    }
+
+    #[test]
+    fn test_threat_columns_empty_when_over() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[0, 1, 0, 1, 0, 1, 0]);
+        assert!(game.winning_columns().is_empty());
+        assert!(game.must_block_columns().is_empty());
+    }
+
+    #[test]
+    fn test_threat_cells() {
+        let mut game = ConnectFourGame::new(GameConfig::default());
+        play(&mut game, &[1, 1, 2, 2, 3, 3]);
+        assert_eq!(game.threat_cells(Player::One), vec![(0, 0), (4, 0)]);
+        assert_eq!(game.threat_cells(Player::Two), vec![(0, 1), (4, 1)]);
+    }
+}
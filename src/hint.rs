@@ -0,0 +1,73 @@
+use crate::game::Player;
+use crate::negamax::NegamaxPlayer;
+use crate::position::Position;
+
+
+const HINT_DEPTH: usize = 8;
+
+
+// Why a column was suggested.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum HintReason {
+    WinsImmediately,
+    BlocksWin,  // The opponent would win in this column on their next move.
+    BestBySearch,
+}
+
+
+// A suggested move for the player to move.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Hint {
+    pub column: usize,
+    pub reason: HintReason,
+}
+
+
+// Suggest a move for the player to move, or None if the board is full. A winning move comes
+// first, then a block of the opponent's winning move, and otherwise the best move found by
+// searching a few moves ahead.
+pub fn suggest(position: &Position) -> Option<Hint> {
+    let player: Player = position.to_move();
+    if let Some(col) = position.winning_columns(player).first() {
+        return Some(Hint { column: *col, reason: HintReason::WinsImmediately });
+    }
+    if let Some(col) = position.winning_columns(player.other()).first() {
+        return Some(Hint { column: *col, reason: HintReason::BlocksWin });
+    }
+    let col: usize = NegamaxPlayer::new(HINT_DEPTH).best_move(position)?;
+    return Some(Hint { column: col, reason: HintReason::BestBySearch });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::test_positions::position;
+
+    #[test]
+    fn test_suggests_win() {
+        // Both players have three in a column; winning comes before blocking.
+        assert_eq!(suggest(&position("121212")),
+                   Some(Hint { column: 0, reason: HintReason::WinsImmediately }));
+    }
+
+    #[test]
+    fn test_suggests_block() {
+        assert_eq!(suggest(&position("12121")),
+                   Some(Hint { column: 0, reason: HintReason::BlocksWin }));
+    }
+
+    #[test]
+    fn test_suggests_search_move() {
+        // Player 2 must take one end of player 1's two in a row before it becomes an open three.
+        let hint = suggest(&position("445")).unwrap();
+        assert_eq!(hint.reason, HintReason::BestBySearch);
+        assert!(hint.column == 2 || hint.column == 5);
+    }
+}
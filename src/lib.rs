@@ -0,0 +1,20 @@
+#![allow(clippy::needless_return)]
+
+pub mod clock;
+pub mod difficulty;
+pub mod game;
+pub mod hint;
+pub mod lobby;
+pub mod mcts;
+pub mod move_source;
+pub mod negamax;
+pub mod network;
+pub mod notation;
+pub mod position;
+pub mod protocol;
+pub mod record;
+pub mod render;
+pub mod rng;
+pub mod save;
+pub mod solver;
+pub mod transposition;
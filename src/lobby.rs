@@ -0,0 +1,653 @@
+// A server that runs many network games at once, with a lobby for finding an opponent.
+//
+// A client connects and sends lobby requests, one per line:
+//
+//   list            The server replies `game ID W H N` for every game waiting for an
+//                   opponent and `playing ID W H N` for every game being played, then `end`.
+//   create [W H N]  Open a game on the standard board, or on a W x H board with N in a row to
+//                   win, and wait for an opponent. The server replies `created ID`.
+//   join ID         Join the open game with the given ID.
+//   play [W H N]    Join the game on that board that has waited longest, or open one if there
+//                   is none.
+//   watch ID        Watch the game being played with the given ID, from its first move.
+//   quit            Leave.
+//
+// A request the server can't handle is answered with `error <message>`. Once two players are
+// paired, the server hosts their game using the messages in `network`, starting with `hello`:
+// the player who opened the game is player 1. Spectators are sent the game as in `network`,
+// starting with `spectate`. A player who leaves or stays silent for too long during a game
+// forfeits it, and open games whose player has left or waited too long are closed. If the
+// server has a time control, every game is played on a clock and the clocks are sent as in
+// `network`.
+
+use crate::clock::Clock;
+use crate::clock::TimeControl;
+use crate::game::ConnectFourGame;
+use crate::game::GameConfig;
+use crate::game::GameState;
+use crate::game::Player;
+use crate::move_source::GameView;
+use crate::move_source::Move;
+use crate::move_source::MoveSource;
+use crate::network::Message;
+use crate::network::RemotePlayer;
+use crate::network::Spectators;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+const DEFAULT_MAX_WAIT: Duration = Duration::from_secs(1800);
+
+
+// How long the server waits for players before giving up on them, and the clocks its games are
+// played on.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct ServerSettings {
+    pub idle_timeout: Duration,  // How long a player may take to send a request or a move.
+    pub max_wait: Duration,  // How long an open game waits for an opponent.
+    pub time_control: Option<TimeControl>,  // None to play without clocks.
+}
+
+
+// A request from a client in the lobby.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Request {
+    List,
+    Create(GameConfig),
+    Join(u64),
+    Play(GameConfig),
+    Watch(u64),
+    Quit,
+}
+
+
+// A reply from the server to a lobby request.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Reply {
+    Game { id: u64, config: GameConfig },  // One of the open games listed.
+    Playing { id: u64, config: GameConfig },  // One of the games in progress listed.
+    End,  // The end of the list.
+    Created(u64),
+    Error(String),
+    Start { config: GameConfig, player: Player },  // The `hello` that starts a game.
+    Spectate { config: GameConfig },  // The greeting that starts watching a game.
+}
+
+
+// A game waiting for an opponent, holding the connection of the player who opened it.
+struct OpenGame {
+    id: u64,
+    config: GameConfig,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    opened: Instant,
+}
+
+
+// A game being played, which spectators can be added to.
+struct RunningGame {
+    id: u64,
+    config: GameConfig,
+    spectators: Spectators,
+}
+
+
+// The games waiting for an opponent, oldest first, and the games being played.
+#[derive(Default)]
+pub struct Lobby {
+    open: Vec<OpenGame>,
+    running: Vec<RunningGame>,
+    next_id: u64,
+}
+
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        return ServerSettings {
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            max_wait: DEFAULT_MAX_WAIT,
+            time_control: None,
+        };
+    }
+}
+
+
+impl Request {
+    // Parse a request line, or return a message explaining why it couldn't be parsed.
+    pub fn parse(line: &str) -> Result<Request, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        return match words.as_slice() {
+            ["list"] => Ok(Request::List),
+            ["create", size @ ..] => Ok(Request::Create(parse_config(size)?)),
+            ["join", id] => id.parse::<u64>()
+                    .map(Request::Join)
+                    .map_err(|_| format!("not a game: {id}")),
+            ["play", size @ ..] => Ok(Request::Play(parse_config(size)?)),
+            ["watch", id] => id.parse::<u64>()
+                    .map(Request::Watch)
+                    .map_err(|_| format!("not a game: {id}")),
+            ["quit"] => Ok(Request::Quit),
+            _ => Err(format!("unknown request: {}", line.trim())),
+        };
+    }
+
+
+    pub fn format(&self) -> String {
+        return match self {
+            Request::List => String::from("list"),
+            Request::Create(config) => format!("create {}", format_config(*config)),
+            Request::Join(id) => format!("join {id}"),
+            Request::Play(config) => format!("play {}", format_config(*config)),
+            Request::Watch(id) => format!("watch {id}"),
+            Request::Quit => String::from("quit"),
+        };
+    }
+}
+
+
+impl Reply {
+    // Parse a reply line, or return None if it isn't one.
+    pub fn parse(line: &str) -> Option<Reply> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        return match words.as_slice() {
+            ["game", id, size @ ..] => Some(Reply::Game {
+                id: id.parse::<u64>().ok()?,
+                config: parse_config(size).ok()?,
+            }),
+            ["playing", id, size @ ..] => Some(Reply::Playing {
+                id: id.parse::<u64>().ok()?,
+                config: parse_config(size).ok()?,
+            }),
+            ["end"] => Some(Reply::End),
+            ["created", id] => Some(Reply::Created(id.parse::<u64>().ok()?)),
+            ["error", ..] => Some(Reply::Error(String::from(line.trim()["error".len()..].trim()))),
+            _ => match Message::parse(line)? {
+                Message::Hello { config, player } => Some(Reply::Start { config, player }),
+                Message::Spectate { config } => Some(Reply::Spectate { config }),
+                _ => None,
+            },
+        };
+    }
+
+
+    pub fn format(&self) -> String {
+        return match self {
+            Reply::Game { id, config } => format!("game {id} {}", format_config(*config)),
+            Reply::Playing { id, config } => format!("playing {id} {}", format_config(*config)),
+            Reply::End => String::from("end"),
+            Reply::Created(id) => format!("created {id}"),
+            Reply::Error(text) => format!("error {text}"),
+            Reply::Start { config, player } =>
+                    Message::Hello { config: *config, player: *player }.format(),
+            Reply::Spectate { config } => Message::Spectate { config: *config }.format(),
+        };
+    }
+}
+
+
+// Parse an optional board size, "W H N", defaulting to the standard board.
+fn parse_config(words: &[&str]) -> Result<GameConfig, String> {
+    let size: Vec<usize> = words.iter()
+            .map(|word| word.parse::<usize>().map_err(|_| format!("not a number: {word}")))
+            .collect::<Result<Vec<usize>, String>>()?;
+    return match size.as_slice() {
+        [] => Ok(GameConfig::default()),
+        [width, height, win_length] => GameConfig::new(*width, *height, *win_length)
+                .map_err(|error| format!("invalid board: {error:?}")),
+        _ => Err(String::from("a board is a width, a height and a win length")),
+    };
+}
+
+
+fn format_config(config: GameConfig) -> String {
+    return format!("{} {} {}", config.width(), config.height(), config.win_length());
+}
+
+
+impl Lobby {
+    pub fn new() -> Self {
+        return Lobby::default();
+    }
+
+
+    // Return the ID and board of every open game, oldest first.
+    pub fn list(&self) -> Vec<(u64, GameConfig)> {
+        return self.open.iter().map(|game| (game.id, game.config)).collect();
+    }
+
+
+    // Return the ID and board of every game being played, oldest first.
+    pub fn playing(&self) -> Vec<(u64, GameConfig)> {
+        return self.running.iter().map(|game| (game.id, game.config)).collect();
+    }
+
+
+    // Open a game for the player on the given connection, telling them its ID.
+    pub fn open(&mut self, config: GameConfig, reader: BufReader<TcpStream>, mut writer: TcpStream)
+            -> io::Result<u64> {
+        self.next_id += 1;
+        let id: u64 = self.next_id;
+        send_reply(&mut writer, &Reply::Created(id))?;
+        self.open.push(OpenGame { id, config, reader, writer, opened: Instant::now() });
+        return Ok(id);
+    }
+
+
+    // Close the open games whose player has left or that have waited longer than the given
+    // time, and return how many were closed.
+    pub fn close_abandoned(&mut self, max_wait: Duration) -> usize {
+        let before: usize = self.open.len();
+        self.open.retain(|game| game.opened.elapsed() < max_wait && is_waiting(game));
+        return before - self.open.len();
+    }
+
+
+    fn take(&mut self, id: u64) -> Option<OpenGame> {
+        let index: usize = self.open.iter().position(|game| game.id == id)?;
+        return Some(self.open.remove(index));
+    }
+
+
+    fn take_matching(&mut self, config: GameConfig) -> Option<OpenGame> {
+        let index: usize = self.open.iter().position(|game| game.config == config)?;
+        return Some(self.open.remove(index));
+    }
+
+
+    // Record that the game has started, and return its spectators.
+    fn start(&mut self, id: u64, config: GameConfig) -> Spectators {
+        let spectators: Spectators = Spectators::new(config);
+        self.running.push(RunningGame { id, config, spectators: spectators.clone() });
+        return spectators;
+    }
+
+
+    fn finish(&mut self, id: u64) {
+        self.running.retain(|game| game.id != id);
+    }
+
+
+    fn spectators(&self, id: u64) -> Option<Spectators> {
+        return self.running.iter()
+                .find(|game| game.id == id)
+                .map(|game| game.spectators.clone());
+    }
+}
+
+
+// Return whether the player of an open game is still connected and waiting. A waiting player
+// has nothing to send, so anything they do send means they've left.
+fn is_waiting(game: &OpenGame) -> bool {
+    if !game.reader.buffer().is_empty() || game.writer.set_nonblocking(true).is_err() {
+        return false;
+    }
+    let waiting: bool = match game.writer.peek(&mut [0; 1]) {
+        Ok(_) => false,  // Either a message or the end of the connection.
+        Err(error) => error.kind() == io::ErrorKind::WouldBlock,
+    };
+    return game.writer.set_nonblocking(false).is_ok() && waiting;
+}
+
+
+fn send_reply(writer: &mut impl Write, reply: &Reply) -> io::Result<()> {
+    writeln!(writer, "{}", reply.format())?;
+    return writer.flush();
+}
+
+
+// Accept clients on the listener and serve each on its own thread, forever.
+pub fn serve(listener: TcpListener, settings: ServerSettings) {
+    let lobby: Arc<Mutex<Lobby>> = Arc::new(Mutex::new(Lobby::new()));
+    let cleanup_lobby: Arc<Mutex<Lobby>> = Arc::clone(&lobby);
+    thread::spawn(move || loop {
+        thread::sleep(CLEANUP_INTERVAL);
+        cleanup_lobby.lock().unwrap().close_abandoned(settings.max_wait);
+    });
+    for stream in listener.incoming().flatten() {
+        let lobby: Arc<Mutex<Lobby>> = Arc::clone(&lobby);
+        thread::spawn(move || serve_client(stream, &lobby, settings));
+    }
+}
+
+
+// Answer the client's lobby requests until they quit, open a game, or start one. A game a
+// client joins is played on the client's thread.
+fn serve_client(stream: TcpStream, lobby: &Mutex<Lobby>, settings: ServerSettings)
+        -> io::Result<()> {
+    stream.set_read_timeout(Some(settings.idle_timeout))?;
+    let mut reader: BufReader<TcpStream> = BufReader::new(stream.try_clone()?);
+    let mut writer: TcpStream = stream;
+    loop {
+        let mut line: String = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Request = match Request::parse(&line) {
+            Ok(request) => request,
+            Err(message) => {
+                send_reply(&mut writer, &Reply::Error(message))?;
+                continue;
+            }
+        };
+        let mut lobby_guard = lobby.lock().unwrap();
+        lobby_guard.close_abandoned(settings.max_wait);
+        let opponent: Option<OpenGame> = match request {
+            Request::List => {
+                for (id, config) in lobby_guard.list() {
+                    send_reply(&mut writer, &Reply::Game { id, config })?;
+                }
+                for (id, config) in lobby_guard.playing() {
+                    send_reply(&mut writer, &Reply::Playing { id, config })?;
+                }
+                send_reply(&mut writer, &Reply::End)?;
+                continue;
+            }
+            Request::Create(config) => {
+                lobby_guard.open(config, reader, writer)?;
+                return Ok(());
+            }
+            Request::Join(id) => match lobby_guard.take(id) {
+                Some(game) => Some(game),
+                None => {
+                    send_reply(&mut writer, &Reply::Error(format!("no open game {id}")))?;
+                    continue;
+                }
+            },
+            Request::Play(config) => match lobby_guard.take_matching(config) {
+                Some(game) => Some(game),
+                None => {
+                    lobby_guard.open(config, reader, writer)?;
+                    return Ok(());
+                }
+            },
+            Request::Watch(id) => match lobby_guard.spectators(id) {
+                Some(spectators) => return spectators.add(writer),
+                None => {
+                    send_reply(&mut writer, &Reply::Error(format!("no game {id} in progress")))?;
+                    continue;
+                }
+            },
+            Request::Quit => return Ok(()),
+        };
+        if let Some(game) = opponent {
+            let (id, config) = (game.id, game.config);
+            let spectators: Spectators = lobby_guard.start(id, config);
+            drop(lobby_guard);
+            let result: io::Result<GameState> =
+                    run_game(game, reader, writer, &spectators, settings.time_control);
+            lobby.lock().unwrap().finish(id);
+            return result.map(|_| ());
+        }
+    }
+}
+
+
+// Host a game between the player who opened it and the player who joined it, sending it to
+// the spectators as it goes, on a clock if there's a time control. A player who leaves
+// forfeits as if they resigned, and a player whose clock runs out loses on time. Draws can be
+// offered and accepted.
+fn run_game(open: OpenGame, reader: BufReader<TcpStream>, writer: TcpStream,
+            spectators: &Spectators, time_control: Option<TimeControl>)
+        -> io::Result<GameState> {
+    let mut players: [RemotePlayer; 2] = [
+        RemotePlayer::with_reader(open.reader, open.writer, open.config, Player::One)?,
+        RemotePlayer::with_reader(reader, writer, open.config, Player::Two)?,
+    ];
+    let mut game: ConnectFourGame = ConnectFourGame::new(open.config);
+    let mut clock: Option<Clock> = time_control.map(Clock::new);
+    loop {
+        let mut state: GameState = game.state();
+        let player: Player = game.active_player();
+        let seat: usize = if player == Player::One { 0 } else { 1 };
+        if state == GameState::InProgress {
+            for remote in &mut players {
+                let _ = remote.send(&Message::Board(game.history().to_vec()));
+            }
+            spectators.board_updated(game.history());
+            if let Some(clock) = &mut clock {
+                clock.start(player);
+                let times: [Duration; 2] = clock.times();
+                for remote in &mut players {
+                    let _ = remote.send(&Message::Clock(times));
+                }
+                spectators.clock_updated(times);
+            }
+            if game.draw_offer() == Some(player.other()) {
+                let _ = players[seat].send(&Message::DrawOffer);
+            }
+            // A remote player is only waited for until their clock runs out.
+            let view: GameView = match &clock {
+                Some(clock) => GameView::with_clock(&game, clock.remaining(player),
+                                                    clock.remaining(player)),
+                None => GameView::new(&game),
+            };
+            match players[seat].choose_move(&view) {
+                _ if clock.as_ref().is_some_and(|clock| clock.is_flagged(player)) =>
+                        state = game.lose_on_time(),
+                Move::Play(_) if clock.as_mut().is_some_and(|clock| !clock.stop(player)) =>
+                        state = game.lose_on_time(),
+                Move::Play(col) => {
+                    game.play_move(player, col).expect("the remote player checks its moves");
+                    continue;
+                }
+                Move::OfferDraw => {
+                    game.offer_draw(player);
+                    continue;
+                }
+                Move::AcceptDraw => {
+                    if !game.accept_draw(player) {
+                        let text: String = String::from("There's no draw offer to accept.");
+                        let _ = players[seat].send(&Message::Error(text));
+                    }
+                    continue;
+                }
+                // Resigning, leaving the game and sending `quit` all forfeit it.
+                _ => state = game.resign(player),
+            }
+        }
+        for remote in &mut players {
+            let _ = remote.send(&Message::Board(game.history().to_vec()));
+            let _ = remote.send(&Message::GameOver(state));
+        }
+        spectators.board_updated(game.history());
+        spectators.game_over(state);
+        return Ok(state);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_source::ScriptedSource;
+    use crate::network;
+    use crate::render::TextRenderer;
+    use std::net::SocketAddr;
+
+    // Start a server on a free local port and return its address.
+    fn start_server() -> SocketAddr {
+        return start_server_with(ServerSettings::default());
+    }
+
+    fn start_server_with(settings: ServerSettings) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, settings));
+        return address;
+    }
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(address: SocketAddr) -> Self {
+            let stream = TcpStream::connect(address).unwrap();
+            return Client { reader: BufReader::new(stream.try_clone().unwrap()), writer: stream };
+        }
+
+        fn request(&mut self, request: Request) -> Reply {
+            writeln!(self.writer, "{}", request.format()).unwrap();
+            return self.reply();
+        }
+
+        fn reply(&mut self) -> Reply {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            return Reply::parse(&line).unwrap();
+        }
+
+        // Play the rest of the game with the given moves and return what was shown.
+        fn play(mut self, config: GameConfig, player: Player, moves: &[usize]) -> String {
+            let mut renderer = TextRenderer::new(Vec::new());
+            network::play_hosted(&mut self.reader, &mut self.writer, config, player,
+                                 &mut ScriptedSource::new(moves), &mut renderer).unwrap();
+            return String::from_utf8(renderer.into_inner()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_requests_round_trip() {
+        let requests = [
+            Request::List,
+            Request::Create(GameConfig::new(9, 7, 5).unwrap()),
+            Request::Join(12),
+            Request::Play(GameConfig::default()),
+            Request::Watch(3),
+            Request::Quit,
+        ];
+        for request in requests {
+            assert_eq!(Request::parse(&request.format()), Ok(request));
+        }
+        assert_eq!(Request::parse("create"), Ok(Request::Create(GameConfig::default())));
+        assert!(Request::parse("create 7 6").is_err());
+        assert!(Request::parse("join me").is_err());
+    }
+
+    #[test]
+    fn test_create_list_and_join() {
+        let address = start_server();
+        let config = GameConfig::new(8, 7, 4).unwrap();
+        let mut host = Client::connect(address);
+        let Reply::Created(id) = host.request(Request::Create(config)) else { panic!() };
+        let mut guest = Client::connect(address);
+        assert_eq!(guest.request(Request::List), Reply::Game { id, config });
+        assert_eq!(guest.reply(), Reply::End);
+        assert!(matches!(guest.request(Request::Join(id + 100)), Reply::Error(_)));
+        assert_eq!(guest.request(Request::Join(id)), Reply::Start { config, player: Player::Two });
+        assert_eq!(host.reply(), Reply::Start { config, player: Player::One });
+        let host_game = thread::spawn(move || host.play(config, Player::One, &[0, 0, 0, 0]));
+        let guest_output = guest.play(config, Player::Two, &[1, 1, 1]);
+        assert!(guest_output.ends_with("Player 1 wins.\nMoves: 1212121\n"), "{guest_output}");
+        assert!(host_game.join().unwrap().ends_with("Player 1 wins.\nMoves: 1212121\n"));
+    }
+
+    #[test]
+    fn test_watch() {
+        let address = start_server();
+        let config = GameConfig::default();
+        let mut host = Client::connect(address);
+        let Reply::Created(id) = host.request(Request::Create(config)) else { panic!() };
+        let mut watcher = Client::connect(address);
+        assert!(matches!(watcher.request(Request::Watch(id)), Reply::Error(_)));
+        let mut guest = Client::connect(address);
+        guest.request(Request::Join(id));
+        host.reply();
+        let host_game = thread::spawn(move || host.play(config, Player::One, &[0, 0, 0, 0]));
+        // Wait for the first move so the spectator joins late.
+        let mut line = String::new();
+        guest.reader.read_line(&mut line).unwrap();
+        assert_eq!(watcher.request(Request::List), Reply::Playing { id, config });
+        assert_eq!(watcher.reply(), Reply::End);
+        assert_eq!(watcher.request(Request::Watch(id)), Reply::Spectate { config });
+        let guest_game = thread::spawn(move || guest.play(config, Player::Two, &[1, 1, 1]));
+        let mut renderer = TextRenderer::new(Vec::new());
+        network::watch_hosted(&mut watcher.reader, config, &mut renderer).unwrap();
+        let output = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(output.ends_with("Player 1 wins.\nMoves: 1212121\n"), "{output}");
+        host_game.join().unwrap();
+        guest_game.join().unwrap();
+    }
+
+    #[test]
+    fn test_auto_pairing() {
+        let address = start_server();
+        let big = GameConfig::new(9, 7, 5).unwrap();
+        let mut first = Client::connect(address);
+        let mut other_board = Client::connect(address);
+        let mut second = Client::connect(address);
+        assert!(matches!(first.request(Request::Play(GameConfig::default())), Reply::Created(_)));
+        assert!(matches!(other_board.request(Request::Play(big)), Reply::Created(_)));
+        let start = second.request(Request::Play(GameConfig::default()));
+        assert_eq!(start, Reply::Start { config: GameConfig::default(), player: Player::Two });
+        // The player who left forfeits.
+        drop(first);
+        let output = second.play(GameConfig::default(), Player::Two, &[]);
+        assert!(output.contains("Player 2 wins."), "{output}");
+    }
+
+    #[test]
+    fn test_loss_on_time() {
+        let time_control = Some(TimeControl::PerMove(Duration::from_millis(50)));
+        let address = start_server_with(ServerSettings { time_control, ..Default::default() });
+        let config = GameConfig::default();
+        let mut host = Client::connect(address);
+        let Reply::Created(id) = host.request(Request::Create(config)) else { panic!() };
+        let mut guest = Client::connect(address);
+        guest.request(Request::Join(id));
+        host.reply();
+        let guest_game = thread::spawn(move || guest.play(config, Player::Two, &[]));
+        // The host never answers, and is flagged without the server waiting any longer.
+        let mut received = Vec::new();
+        while !matches!(received.last(), Some(Message::GameOver(_))) {
+            received.push(network::receive(&mut host.reader).unwrap());
+        }
+        assert_eq!(received.last(), Some(&Message::GameOver(GameState::OutOfTime(Player::One))));
+        let output = guest_game.join().unwrap();
+        assert!(output.ends_with("Player 1 ran out of time. Player 2 wins.\nMoves: \n"),
+                "{output}");
+    }
+
+    #[test]
+    fn test_close_abandoned() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut lobby = Lobby::new();
+        let mut clients = Vec::new();
+        for _ in 0..2 {
+            clients.push(TcpStream::connect(listener.local_addr().unwrap()).unwrap());
+            let (stream, _) = listener.accept().unwrap();
+            let reader = BufReader::new(stream.try_clone().unwrap());
+            lobby.open(GameConfig::default(), reader, stream).unwrap();
+        }
+        assert_eq!(lobby.close_abandoned(Duration::from_secs(60)), 0);
+        drop(clients.remove(0));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(lobby.close_abandoned(Duration::from_secs(60)), 1);
+        assert_eq!(lobby.list().len(), 1);
+        assert_eq!(lobby.close_abandoned(Duration::ZERO), 1);
+        assert!(lobby.list().is_empty());
+    }
+}
@@ -1,9 +1,1180 @@
-mod game;
+#![allow(clippy::needless_return)]
+
 mod printer;
 
+use printer::TerminalRenderer;
+
+use rust_connect_4::clock::Clock;
+use rust_connect_4::clock::TimeControl;
+use rust_connect_4::difficulty::DIFFICULTIES;
+use rust_connect_4::difficulty::Difficulty;
+use rust_connect_4::difficulty::DifficultyPlayer;
+use rust_connect_4::game::ConnectFourGame;
+use rust_connect_4::game::GameConfig;
+use rust_connect_4::game::GameState;
+use rust_connect_4::game::Player;
+use rust_connect_4::hint;
+use rust_connect_4::hint::Hint;
+use rust_connect_4::hint::HintReason;
+use rust_connect_4::lobby::Reply;
+use rust_connect_4::lobby::Request;
+use rust_connect_4::mcts::Budget;
+use rust_connect_4::mcts::MctsPlayer;
+use rust_connect_4::move_source::GameView;
+use rust_connect_4::move_source::Move;
+use rust_connect_4::move_source::MoveSource;
+use rust_connect_4::negamax::NegamaxPlayer;
+use rust_connect_4::network;
+use rust_connect_4::network::RemotePlayer;
+use rust_connect_4::network::RemoteRenderer;
+use rust_connect_4::network::Spectators;
+use rust_connect_4::notation;
+use rust_connect_4::protocol;
+use rust_connect_4::protocol::ExternalEngine;
+use rust_connect_4::protocol::Limits;
+use rust_connect_4::record;
+use rust_connect_4::record::GameRecord;
+use rust_connect_4::render::Renderer;
+use rust_connect_4::save;
+use rust_connect_4::save::SaveError;
+use rust_connect_4::solver::Outcome;
+use rust_connect_4::solver::Solution;
+use rust_connect_4::solver::SolveError;
+use rust_connect_4::solver::Solver;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::process::exit;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::OnceLock;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use std::time::Instant;
+
+
+const COMMAND_ACCEPT: &str = "accept";
+const COMMAND_DRAW: &str = "draw";
+const COMMAND_HINT: &str = "h";
+const COMMAND_LOAD: &str = "load";
+const COMMAND_QUIT: &str = "q";
+const COMMAND_REDO: &str = "r";
+const COMMAND_RESIGN: &str = "resign";
+const COMMAND_SAVE: &str = "save";
+const COMMAND_UNDO: &str = "u";
+const DEFAULT_COMPUTER_DEPTH: usize = 7;
+const DEFAULT_MCTS_ITERATIONS: usize = 20_000;
+const SUBCOMMAND_CONNECT: &str = "connect";
+const SUBCOMMAND_ENGINE: &str = "engine";
+const SUBCOMMAND_HOST: &str = "host";
+const SUBCOMMAND_JOIN: &str = "join";
+const SUBCOMMAND_SOLVE: &str = "solve";
+const USAGE: &str = "Usage: rust-connect-4 [--width N] [--height N] [--win N] [--load FILE]
+                     [--record FILE] [--player1 NAME] [--player2 NAME]
+                     [--opponent human|computer|mcts|perfect] [--first human|computer]
+                     [--depth N] [--iterations N] [--think-time MS]
+                     [--difficulty beginner|easy|medium|hard|expert] [--no-hints]
+                     [--engine COMMAND] [--clock CONTROL]
+       rust-connect-4 host PORT [--width N] [--height N] [--win N] [--record FILE]
+                     [--player1 NAME] [--player2 NAME] [--no-hints] [--clock CONTROL]
+       rust-connect-4 join ADDRESS
+       rust-connect-4 connect ADDRESS
+       rust-connect-4 solve [MOVES]
+       rust-connect-4 engine";
+
+
+// The ways the computer can choose its moves.
+#[derive(Debug)]
+#[derive(PartialEq)]
+enum Ai {
+    // Search a fixed number of moves ahead.
+    Negamax(NegamaxPlayer),
+    // Monte Carlo tree search for a number of iterations or a length of time.
+    Mcts(MctsPlayer),
+    // Play perfectly using the solver. Only supports the standard board.
+    Perfect,
+    // Play at a difficulty level, which can be changed between games.
+    Level(Difficulty),
+    // Ask an engine in another process, started with the given command, using the engine
+    // protocol.
+    External { command: Vec<String>, limits: Limits },
+}
+
+
+// A computer opponent and the player whose moves it makes.
+#[derive(Debug)]
+#[derive(PartialEq)]
+struct Computer {
+    player: Player,
+    ai: Ai,
+}
+
+
+// Options given on the command line.
+#[derive(Debug)]
+#[derive(PartialEq)]
+struct Options {
+    config: GameConfig,
+    load: Option<String>,  // A saved game to resume, which brings its own config.
+    record: Option<String>,  // An archive to append the record of the game to.
+    player_names: [String; 2],
+    computer: Option<Computer>,  // None when two humans play each other.
+    hints: bool,  // Whether players may ask for a hint, which is turned off for ranked games.
+    time_control: Option<TimeControl>,  // The clocks the game is played on, if any.
+}
+
+
+// A person choosing moves at the terminal.
+struct Human {
+    hints: bool,  // Whether the hint command is allowed.
+    takebacks: bool,  // Whether undo, redo and load are allowed, which they aren't over a network.
+}
+
+
+// An answer to the question of whether to play another game.
+#[derive(Debug)]
+#[derive(PartialEq)]
+enum Replay {
+    Stop,
+    Again,
+    AgainAt(Difficulty),  // Play again against the computer at a different difficulty.
+}
+
 
 fn main() {
-    let mut game = game::ConnectFourGame::new();
-    game.play();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == SUBCOMMAND_ENGINE) {
+        if let Err(error) = protocol::run(std::io::stdin().lock(), std::io::stdout().lock()) {
+            eprintln!("{error}");
+            exit(1);
+        }
+        return;
+    }
+    if args.first().is_some_and(|arg| arg == SUBCOMMAND_SOLVE) {
+        match solve_command(&args[1..]) {
+            Ok(report) => println!("{report}"),
+            Err(message) => {
+                eprintln!("{message}");
+                exit(1);
+            }
+        }
+        return;
+    }
+    let network_command = match args.first().map(String::as_str) {
+        Some(SUBCOMMAND_HOST) => Some(host_command(&args[1..])),
+        Some(SUBCOMMAND_JOIN) => Some(join_command(&args[1..])),
+        Some(SUBCOMMAND_CONNECT) => Some(connect_command(&args[1..])),
+        _ => None,
+    };
+    if let Some(result) = network_command {
+        if let Err(message) = result {
+            eprintln!("{message}");
+            exit(1);
+        }
+        return;
+    }
+    let options: Options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
+    let mut game: ConnectFourGame = match &options.load {
+        Some(path) => match save::load_from_file(path) {
+            Ok(game) => game,
+            Err(error) => {
+                eprintln!("{}", describe_save_error(&error, path));
+                exit(1);
+            }
+        },
+        None => ConnectFourGame::new(options.config),
+    };
+    if options.computer.as_ref().is_some_and(|computer| computer.ai == Ai::Perfect)
+            && game.config() != GameConfig::default() {
+        eprintln!("The perfect opponent only plays on the standard 7x6 board with 4 to win.");
+        exit(2);
+    }
+    let mut computer: Option<Computer> = options.computer;
+    loop {
+        let mut sources: [Box<dyn MoveSource>; 2] =
+                match move_sources(computer.as_ref(), game.config(), options.hints) {
+                    Ok(sources) => sources,
+                    Err(message) => {
+                        eprintln!("{message}");
+                        exit(1);
+                    }
+                };
+        let mut clock: Option<Clock> = options.time_control.map(Clock::new);
+        let mut renderer: TerminalRenderer = TerminalRenderer::new(options.hints, true);
+        play(&mut game, &mut sources, &mut renderer, clock.as_mut());
+        if let Some(path) = &options.record {
+            archive_game(&game, &options.player_names, options.time_control, path);
+        }
+        if game.state() == GameState::Aborted {
+            return;  // The players quit.
+        }
+        let leveled: Option<&mut Difficulty> = match &mut computer {
+            Some(Computer { ai: Ai::Level(difficulty), .. }) => Some(difficulty),
+            _ => None,
+        };
+        match ask_replay(leveled.is_some()) {
+            Replay::Stop => return,
+            Replay::Again => {},
+            Replay::AgainAt(difficulty) => *leveled.unwrap() = difficulty,
+        }
+        game = ConnectFourGame::new(game.config());
+    }
+}
+
+
+// Parse the command line arguments (excluding the program name).
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let default_config: GameConfig = GameConfig::default();
+    let mut width: usize = default_config.width();
+    let mut height: usize = default_config.height();
+    let mut win_length: usize = default_config.win_length();
+    let mut load: Option<String> = None;
+    let mut record: Option<String> = None;
+    let mut player_names: [String; 2] = [String::from("Player 1"), String::from("Player 2")];
+    let mut opponent: String = String::from("human");
+    let mut computer_first: bool = false;
+    let mut depth: usize = DEFAULT_COMPUTER_DEPTH;
+    let mut budget: Budget = Budget::Iterations(DEFAULT_MCTS_ITERATIONS);
+    let mut difficulty: Option<Difficulty> = None;
+    let mut hints: bool = true;
+    let mut engine: Option<Vec<String>> = None;
+    let mut time_control: Option<TimeControl> = None;
+    let mut i: usize = 0;
+    while i < args.len() {
+        let flag: &str = args[i].as_str();
+        if flag == "--no-hints" {
+            hints = false;
+            i += 1;
+            continue;
+        }
+        let value: &String = args.get(i + 1).ok_or(format!("Missing value for {flag}"))?;
+        match flag {
+            "--width" => width = parse_number_option(flag, value)?,
+            "--height" => height = parse_number_option(flag, value)?,
+            "--win" => win_length = parse_number_option(flag, value)?,
+            "--load" => load = Some(value.clone()),
+            "--record" => record = Some(value.clone()),
+            "--player1" => player_names[0] = value.clone(),
+            "--player2" => player_names[1] = value.clone(),
+            "--opponent" => opponent = parse_opponent_option(flag, value)?,
+            "--first" => computer_first = parse_seat_option(flag, value)?,
+            "--depth" => depth = parse_number_option(flag, value)?,
+            "--iterations" => budget = Budget::Iterations(parse_number_option(flag, value)?),
+            "--difficulty" => difficulty = Some(Difficulty::from_name(value).ok_or(
+                    format!("Invalid value for {flag}: {value} (expected {})",
+                            difficulty_names()))?),
+            "--clock" => time_control = Some(TimeControl::parse(value).ok_or(
+                    format!("Invalid value for {flag}: {value} (expected SECONDS, \
+                             SECONDS+INCREMENT or 1/SECONDS)"))?),
+            "--engine" => engine = Some(value.split_whitespace().map(String::from).collect()),
+            "--think-time" => budget = Budget::Time(Duration::from_millis(
+                    parse_number_option(flag, value)? as u64)),
+            _ => return Err(format!("Unknown option: {flag}")),
+        }
+        i += 2;
+    }
+    let config: GameConfig = GameConfig::new(width, height, win_length)
+            .map_err(|e| format!("Invalid board: {:?}", e))?;
+    let player: Player = if computer_first { Player::One } else { Player::Two };
+    let limits: Limits = match budget {
+        Budget::Time(movetime) => Limits { depth: None, movetime: Some(movetime) },
+        Budget::Iterations(_) => Limits { depth: Some(depth), movetime: None },
+    };
+    // A difficulty level or an engine command picks a computer opponent that plays that way, so
+    // they can't be combined with each other or with an opponent that plays its own way.
+    let computer: Option<Computer> = match (opponent.as_str(), difficulty, engine) {
+        (_, _, Some(command)) if command.is_empty() => return Err(String::from("Empty --engine")),
+        (_, Some(_), Some(_)) =>
+                return Err(String::from("--difficulty and --engine can't be used together")),
+        ("mcts" | "perfect", Some(_), _) | ("mcts" | "perfect", _, Some(_)) =>
+                return Err(format!("--opponent {opponent} can't be used with --difficulty or \
+                                    --engine")),
+        ("human" | "computer", _, Some(command)) =>
+                Some(Computer { player, ai: Ai::External { command, limits } }),
+        ("human" | "computer", Some(difficulty), _) =>
+                Some(Computer { player, ai: Ai::Level(difficulty) }),
+        ("computer", None, _) =>
+                Some(Computer { player, ai: Ai::Negamax(NegamaxPlayer::new(depth)) }),
+        ("mcts", _, _) => Some(Computer { player, ai: Ai::Mcts(MctsPlayer::new(budget)) }),
+        ("perfect", _, _) => Some(Computer { player, ai: Ai::Perfect }),
+        _ => None,
+    };
+    return Ok(Options { config, load, record, player_names, computer, hints, time_control });
+}
+
+
+// Return the names of the difficulty levels, for messages.
+fn difficulty_names() -> String {
+    return DIFFICULTIES.map(|difficulty| difficulty.name()).join(", ");
+}
+
+
+// Parse "human" or "computer", returning whether it was "computer".
+fn parse_seat_option(flag: &str, value: &str) -> Result<bool, String> {
+    return match value {
+        "human" => Ok(false),
+        "computer" => Ok(true),
+        _ => Err(format!("Invalid value for {flag}: {value} (expected human or computer)")),
+    };
+}
+
+
+fn parse_opponent_option(flag: &str, value: &str) -> Result<String, String> {
+    return match value {
+        "human" | "computer" | "mcts" | "perfect" => Ok(String::from(value)),
+        _ => Err(format!("Invalid value for {flag}: {value} \
+                          (expected human, computer, mcts or perfect)")),
+    };
+}
+
+
+fn parse_number_option(flag: &str, value: &str) -> Result<usize, String> {
+    return value.parse::<usize>().map_err(|_| format!("Invalid value for {flag}: {value}"));
+}
+
+
+// Return the move sources for player 1 and player 2: people at the terminal, except for the
+// computer's player, if any. Fails if an external engine can't be started.
+fn move_sources(computer: Option<&Computer>, config: GameConfig, hints: bool)
+        -> Result<[Box<dyn MoveSource>; 2], String> {
+    let human = || -> Box<dyn MoveSource> { Box::new(Human { hints, takebacks: true }) };
+    let mut sources: [Box<dyn MoveSource>; 2] = [human(), human()];
+    if let Some(computer) = computer {
+        sources[seat(computer.player)] = match &computer.ai {
+            Ai::Negamax(ai) => Box::new(ai.clone()),
+            Ai::Mcts(ai) => Box::new(ai.clone()),
+            Ai::Perfect => Box::new(Solver::new()),
+            Ai::Level(difficulty) => Box::new(DifficultyPlayer::new(*difficulty)),
+            Ai::External { command, limits } =>
+                    Box::new(ExternalEngine::spawn(&command[0], &command[1..], config, *limits)
+                            .map_err(|error| format!("Couldn't start {}: {error}", command[0]))?),
+        };
+    }
+    return Ok(sources);
+}
+
+
+// Return the index of the given player's move source.
+fn seat(player: Player) -> usize {
+    return match player {
+        Player::One => 0,
+        Player::Two => 1,
+    };
+}
+
+
+// Run the game until one player wins, a tie is reached, a player quits or a player's clock runs
+// out, taking each player's moves from their move source and showing the game with the
+// renderer.
+fn play(game: &mut ConnectFourGame,
+        sources: &mut [Box<dyn MoveSource>; 2],
+        renderer: &mut dyn Renderer,
+        mut clock: Option<&mut Clock>) {
+    loop {
+        renderer.board_updated(game);
+        if let Some(clock) = clock.as_deref_mut() {
+            clock.start(game.active_player());
+            renderer.clock_updated(clock.times());
+        }
+        let turn_end_state: GameState = turn(game, sources, renderer, clock.as_deref_mut());
+        if turn_end_state != GameState::InProgress {
+            renderer.game_over(game, turn_end_state);
+            return;
+        }
+    }
+}
+
+
+// Append the record of the finished game to the archive at the given path.
+fn archive_game(game: &ConnectFourGame, player_names: &[String; 2],
+                time_control: Option<TimeControl>, path: &str) {
+    let mut game_record: GameRecord = GameRecord::from_game(game);
+    game_record.set_header(record::HEADER_PLAYER1, &player_names[0]);
+    game_record.set_header(record::HEADER_PLAYER2, &player_names[1]);
+    if let Some(time_control) = time_control {
+        game_record.set_header(record::HEADER_TIME_CONTROL, &time_control.format());
+    }
+    let appended = OpenOptions::new().create(true).append(true).open(path)
+            .and_then(|mut file| file.write_all(game_record.to_string().as_bytes()));
+    match appended {
+        Ok(()) => println!("Game recorded in {path}."),
+        Err(error) => println!("Couldn't record the game in {path}: {error}"),
+    }
+}
+
+
+// Ask the move source of the player to move for moves until one changes the game, and return
+// the resulting game state. Undo and redo skip over the turns of sources that aren't
+// interactive, so a person playing the computer gets their own turn back. Resigning, agreeing
+// to a draw and quitting end the game, and a draw offered to the computer is answered at once,
+// while a person answers it on their turn. On a clock, the player's clock runs from the start
+// of their turn; a player who answers after it has run out loses on time, and a person or
+// program that hasn't answered by then is no longer waited for.
+fn turn(game: &mut ConnectFourGame,
+        sources: &mut [Box<dyn MoveSource>; 2],
+        renderer: &mut dyn Renderer,
+        mut clock: Option<&mut Clock>) -> GameState {
+    let player: usize = seat(game.active_player());
+    if sources[player].is_interactive() {
+        renderer.prompt(game);
+    } else {
+        renderer.message("The computer is thinking...");
+    }
+    loop {
+        let chosen: Move = match clock.as_deref() {
+            Some(clock) => {
+                // Each player makes about half of the moves left on the board.
+                let cells: usize = game.config().width() * game.config().height();
+                let moves_left: usize = (cells - game.history().len()) / 2;
+                let think_time: Duration = clock.think_time(game.active_player(), moves_left);
+                let time_left: Duration = clock.remaining(game.active_player());
+                sources[player].choose_move(&GameView::with_clock(game, think_time, time_left))
+            }
+            None => sources[player].choose_move(&GameView::new(game)),
+        };
+        if clock.as_deref().is_some_and(|clock| clock.is_flagged(game.active_player())) {
+            return game.lose_on_time();
+        }
+        match chosen {
+            Move::Play(col) => match game.play_move(game.active_player(), col) {
+                Ok(state) => {
+                    if let Some(clock) = clock.as_deref_mut() {
+                        clock.stop(game.active_player().other());
+                    }
+                    return state;
+                }
+                Err(error) => renderer.invalid_move(col, error),
+            },
+            Move::Undo | Move::Redo => {
+                let step = |game: &mut ConnectFourGame| {
+                    if chosen == Move::Undo { game.undo() } else { game.redo() }
+                };
+                if step(game) {
+                    while !sources[seat(game.active_player())].is_interactive() && step(game) {}
+                    return game.state();  // Show the board as it is after the undo or redo.
+                }
+                let step_name: &str = if chosen == Move::Undo { "undo" } else { "redo" };
+                renderer.message(&format!("Nothing to {step_name}."));
+            },
+            Move::Load(path) => match save::load_from_file(&path) {
+                Ok(loaded) => {
+                    *game = loaded;
+                    return game.state();  // Show the loaded board.
+                }
+                Err(error) => renderer.message(&describe_save_error(&error, &path)),
+            },
+            Move::Resign => return game.resign(game.active_player()),
+            Move::OfferDraw => {
+                let offerer: Player = game.active_player();
+                game.offer_draw(offerer);
+                let opponent: &mut Box<dyn MoveSource> = &mut sources[seat(offerer.other())];
+                if opponent.is_interactive() {
+                    renderer.message(&format!("Player {} offers a draw.", seat(offerer) + 1));
+                } else if opponent.accept_draw(&GameView::new(game)) {
+                    game.accept_draw(offerer.other());
+                    return game.state();
+                } else {
+                    renderer.message("The computer declines the draw.");
+                }
+            },
+            Move::AcceptDraw => {
+                if game.accept_draw(game.active_player()) {
+                    return game.state();
+                }
+                renderer.message("There's no draw offer to accept.");
+            },
+            Move::Quit => return game.abort(),
+        }
+    }
+}
+
+
+impl MoveSource for Human {
+    // Read commands from stdin until one is a move. Hints and saving are handled here, since
+    // they don't change the game.
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        loop {
+            let input: String = match get_player_input(&view.config(), view.deadline()) {
+                Some(input) => input,
+                None => return Move::Quit,  // Out of input, or out of time.
+            };
+            if input == COMMAND_QUIT {
+                return Move::Quit;
+            }
+            if input == COMMAND_RESIGN {
+                return Move::Resign;
+            }
+            if input == COMMAND_DRAW {
+                return Move::OfferDraw;
+            }
+            if input == COMMAND_ACCEPT {
+                return Move::AcceptDraw;
+            }
+            let takeback: bool = input == COMMAND_UNDO || input == COMMAND_REDO
+                    || input.starts_with(COMMAND_LOAD);
+            if takeback && !self.takebacks {
+                println!("That isn't available in a network game.");
+                continue;
+            }
+            if input == COMMAND_UNDO {
+                return Move::Undo;
+            }
+            if input == COMMAND_REDO {
+                return Move::Redo;
+            }
+            if input == COMMAND_HINT {
+                if !self.hints {
+                    println!("Hints are turned off for this game.");
+                } else if let Some(hint) = hint::suggest(view.position()) {
+                    println!("{}", describe_hint(hint));
+                }
+                continue;
+            }
+            if let Some((command, path)) = input.split_once(' ') {
+                if command == COMMAND_SAVE {
+                    match save::save_to_file(view.game(), path) {
+                        Ok(()) => println!("Game saved to {path}."),
+                        Err(error) => println!("{}", describe_save_error(&error, path)),
+                    }
+                    continue;
+                }
+                return Move::Load(String::from(path));
+            }
+            return Move::Play(convert_input_to_column(input));
+        }
+    }
+
+
+    fn is_interactive(&self) -> bool {
+        return true;
+    }
+}
+
+
+// Host a game for a player on another machine to join, playing as player 1 at this terminal.
+// The arguments are the port to listen on and then the board, record and hint options.
+fn host_command(args: &[String]) -> Result<(), String> {
+    let port: u16 = match args.first().map(|port| port.parse::<u16>()) {
+        Some(Ok(port)) => port,
+        _ => return Err(format!("Usage: rust-connect-4 {SUBCOMMAND_HOST} PORT [OPTIONS]")),
+    };
+    let options: Options = parse_options(&args[1..])?;
+    if options.computer.is_some() || options.load.is_some() {
+        return Err(String::from("A hosted game is a new game between two people."));
+    }
+    let listener: TcpListener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|error| format!("Couldn't listen on port {port}: {error}"))?;
+    println!("Waiting for a player to join on port {port}...");
+    let (stream, _) = listener.accept()
+            .map_err(|error| format!("Couldn't accept a player: {error}"))?;
+    let mut game: ConnectFourGame = ConnectFourGame::new(options.config);
+    let remote: RemotePlayer = stream.try_clone()
+            .and_then(|stream| RemotePlayer::new(stream, options.config, Player::Two))
+            .map_err(|error| format!("Couldn't greet the player: {error}"))?;
+    let mut sources: [Box<dyn MoveSource>; 2] =
+            [Box::new(Human { hints: options.hints, takebacks: false }), Box::new(remote)];
+    let terminal: TerminalRenderer = TerminalRenderer::new(options.hints, false);
+    let mut renderer: RemoteRenderer<TerminalRenderer> =
+            RemoteRenderer::new(terminal, stream, Player::Two, options.config);
+    // Everyone who connects after the player watches.
+    let spectators: Spectators = renderer.spectators();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = spectators.add(stream);
+        }
+    });
+    let mut clock: Option<Clock> = options.time_control.map(Clock::new);
+    play(&mut game, &mut sources, &mut renderer, clock.as_mut());
+    if let Some(path) = &options.record {
+        archive_game(&game, &options.player_names, options.time_control, path);
+    }
+    return Ok(());
+}
+
+
+// Join a game hosted at the given address, playing as player 2 at this terminal.
+fn join_command(args: &[String]) -> Result<(), String> {
+    let address: &str = match args {
+        [address] => address,
+        _ => return Err(format!("Usage: rust-connect-4 {SUBCOMMAND_JOIN} ADDRESS")),
+    };
+    let stream: TcpStream = TcpStream::connect(address)
+            .map_err(|error| format!("Couldn't connect to {address}: {error}"))?;
+    let mut human: Human = Human { hints: true, takebacks: false };
+    return network::join(stream, &mut human, &mut TerminalRenderer::new(true, false))
+            .map_err(|error| format!("Lost the game at {address}: {error}"));
+}
+
+
+// Connect to a game server, find an opponent in its lobby, and play them at this terminal.
+fn connect_command(args: &[String]) -> Result<(), String> {
+    let address: &str = match args {
+        [address] => address,
+        _ => return Err(format!("Usage: rust-connect-4 {SUBCOMMAND_CONNECT} ADDRESS")),
+    };
+    let lost = |error: io::Error| format!("Lost the connection to {address}: {error}");
+    let stream: TcpStream = TcpStream::connect(address)
+            .map_err(|error| format!("Couldn't connect to {address}: {error}"))?;
+    let mut reader: BufReader<TcpStream> = BufReader::new(stream.try_clone().map_err(lost)?);
+    let mut writer: TcpStream = stream;
+    println!("Connected to {address}. \
+              Enter list, create [W H N], join ID, play [W H N], watch ID or quit.");
+    loop {
+        let line: String = match read_stdin_line(None) {
+            Some(line) => line,
+            None => return Ok(()),
+        };
+        let request: Request = match Request::parse(&line) {
+            Ok(request) => request,
+            Err(message) => {
+                println!("{message}");
+                continue;
+            }
+        };
+        writeln!(writer, "{}", request.format()).map_err(lost)?;
+        if request == Request::Quit {
+            return Ok(());
+        }
+        let mut listed: usize = 0;
+        loop {
+            let mut reply_line: String = String::new();
+            if reader.read_line(&mut reply_line).map_err(lost)? == 0 {
+                return Err(format!("{address} closed the connection."));
+            }
+            match Reply::parse(&reply_line) {
+                Some(Reply::Game { id, config }) => {
+                    listed += 1;
+                    println!("Game {id}: {}x{} board, {} in a row to win",
+                             config.width(), config.height(), config.win_length());
+                }
+                Some(Reply::Playing { id, config }) => {
+                    listed += 1;
+                    println!("Game {id}: {}x{} board, {} in a row to win, being played",
+                             config.width(), config.height(), config.win_length());
+                }
+                Some(Reply::End) => {
+                    if listed == 0 {
+                        println!("There are no games.");
+                    }
+                    break;
+                }
+                Some(Reply::Created(id)) =>
+                        println!("Opened game {id}. Waiting for an opponent..."),
+                Some(Reply::Error(text)) => {
+                    println!("{text}");
+                    break;
+                }
+                Some(Reply::Start { config, player }) => {
+                    let mut human: Human = Human { hints: true, takebacks: false };
+                    let mut renderer: TerminalRenderer = TerminalRenderer::new(true, false);
+                    return network::play_hosted(&mut reader, &mut writer, config, player,
+                                                &mut human, &mut renderer)
+                            .map_err(lost);
+                }
+                Some(Reply::Spectate { config }) => {
+                    let mut renderer: TerminalRenderer = TerminalRenderer::new(false, false);
+                    return network::watch_hosted(&mut reader, config, &mut renderer)
+                            .map_err(lost);
+                }
+                None => return Err(format!("Unexpected reply from {address}: {}",
+                                           reply_line.trim())),
+            }
+        }
+    }
+}
+
+
+// Solve the position reached by the given moves (in move sequence notation) on the standard
+// board, returning a report of the result with perfect play and a best move.
+fn solve_command(args: &[String]) -> Result<String, String> {
+    let moves: &str = match args {
+        [] => "",
+        [moves] => moves,
+        _ => return Err(String::from("Usage: rust-connect-4 solve [MOVES]")),
+    };
+    let game: ConnectFourGame = notation::parse(GameConfig::default(), moves)
+            .map_err(|e| format!("Invalid moves: {:?}", e))?;
+    let mut solver: Solver = Solver::new();
+    let solution: Solution = solver.solve(game.position()).map_err(describe_solve_error)?;
+    let col: usize = solver.best_move(game.position()).map_err(describe_solve_error)?;
+    let player: usize = if game.active_player() == Player::One { 1 } else { 2 };
+    let result: String = match solution.outcome {
+        Outcome::Win => format!("wins in {}", count_moves(solution.moves)),
+        Outcome::Loss => format!("loses in {}", count_moves(solution.moves)),
+        Outcome::Draw => String::from("draws"),
+    };
+    return Ok(format!("Player {player} to move {result} with perfect play. Best move: column {}.",
+                      col + 1));
 }
 
+
+fn count_moves(moves: usize) -> String {
+    return if moves == 1 { String::from("1 move") } else { format!("{moves} moves") };
+}
+
+
+fn describe_solve_error(error: SolveError) -> String {
+    return match error {
+        SolveError::UnsupportedConfig =>
+                String::from("The solver only supports the standard 7x6 board with 4 to win."),
+        SolveError::GameOver => String::from("The game is already over."),
+        SolveError::OutOfTime => String::from("The solver ran out of time."),
+    };
+}
+
+
+// Ask whether to play another game until a valid answer is given. Against a computer playing
+// at a difficulty level, the answer can also be a new level to play at.
+fn ask_replay(leveled: bool) -> Replay {
+    if leveled {
+        println!("Play again? Enter y or n, or a difficulty to play again at ({}).",
+                 difficulty_names());
+    } else {
+        println!("Play again? Enter y or n.");
+    }
+    loop {
+        let line: String = match read_stdin_line(None) {
+            Some(line) => line,
+            None => return Replay::Stop,  // No more input.
+        };
+        if let Some(replay) = parse_replay(line.trim(), leveled) {
+            return replay;
+        }
+        println!("Please enter y or n{}.", if leveled { ", or a difficulty" } else { "" });
+    }
+}
+
+
+fn parse_replay(input: &str, leveled: bool) -> Option<Replay> {
+    return match input.to_lowercase().as_str() {
+        "y" | "yes" => Some(Replay::Again),
+        "n" | "no" => Some(Replay::Stop),
+        _ if leveled => Difficulty::from_name(input).map(Replay::AgainAt),
+        _ => None,
+    };
+}
+
+
+fn describe_hint(hint: Hint) -> String {
+    let reason: &str = match hint.reason {
+        HintReason::WinsImmediately => "wins immediately",
+        HintReason::BlocksWin => "blocks a win",
+        HintReason::BestBySearch => "best by search",
+    };
+    return format!("Hint: play column {} ({reason}).", hint.column + 1);
+}
+
+
+// Return a message explaining why the game at the given path couldn't be saved or loaded.
+fn describe_save_error(error: &SaveError, path: &str) -> String {
+    return match error {
+        SaveError::Io(error) => format!("Couldn't access {path}: {error}"),
+        SaveError::Malformed { line, message } =>
+                format!("{path} isn't a valid saved game (line {line}: {message})."),
+        SaveError::IllegalMove { column, error } =>
+                format!("{path} contains an illegal move in column {column}: {:?}", error),
+    };
+}
+
+
+// Read lines from stdin until one is valid input, and return it. Return None at the end of the
+// input or once the deadline, if any, has passed.
+fn get_player_input(config: &GameConfig, deadline: Option<Instant>) -> Option<String> {
+    loop {
+        let line: String = read_stdin_line(deadline)?;
+        let input: String = String::from(line.trim());
+        if is_valid_input(&input, config) {
+            return Some(input);
+        }
+        println!("Invalid move. Please try again.");
+    }
+}
+
+
+// Read a line from stdin, without its newline. Return None at the end of the input or once the
+// deadline, if any, has passed. Stdin is read on a thread of its own so that waiting for a line
+// can time out; every read of stdin goes through here so that no line is lost to that thread.
+fn read_stdin_line(deadline: Option<Instant>) -> Option<String> {
+    static LINES: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+    let lines: &Mutex<Receiver<String>> = LINES.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in io::stdin().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        return Mutex::new(receiver);
+    });
+    let lines: MutexGuard<Receiver<String>> = lines.lock().unwrap();
+    return match deadline {
+        Some(deadline) =>
+                lines.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok(),
+        None => lines.recv().ok(),
+    };
+}
+
+
+// Return whether the given input is a valid game control: a command or a column number between
+// 1 and the board width. The save and load commands take a file path.
+fn is_valid_input(input: &str, config: &GameConfig) -> bool {
+    let commands: [&str; 7] = [COMMAND_ACCEPT, COMMAND_DRAW, COMMAND_HINT, COMMAND_QUIT,
+                               COMMAND_REDO, COMMAND_RESIGN, COMMAND_UNDO];
+    if commands.contains(&input) {
+        return true;
+    }
+    if let Some((command, path)) = input.split_once(' ') {
+        return (command == COMMAND_SAVE || command == COMMAND_LOAD) && !path.trim().is_empty();
+    }
+    return match input.parse::<usize>() {
+        Ok(col) => 1 <= col && col <= config.width(),
+        Err(_) => false,
+    };
+}
+
+
+// Convert the given column input (1-indexed, string) into a column index (0-indexed, usize).
+fn convert_input_to_column(input: String) -> usize {
+    let col: usize = input.parse::<usize>().unwrap() - 1;
+    return col;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_connect_4::move_source::ScriptedSource;
+    use rust_connect_4::render::TextRenderer;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        return list.iter().map(|s| s.to_string()).collect();
+    }
+
+    #[test]
+    fn test_accept_valid_input() {
+        assert!(is_valid_input("2", &GameConfig::default()));
+    }
+
+    #[test]
+    fn test_accept_valid_input_quit() {
+        assert!(is_valid_input("q", &GameConfig::default()));
+    }
+
+    #[test]
+    fn test_accept_valid_input_hint() {
+        assert!(is_valid_input("h", &GameConfig::default()));
+    }
+
+    #[test]
+    fn test_accept_valid_input_undo_redo() {
+        assert!(is_valid_input("u", &GameConfig::default()));
+        assert!(is_valid_input("r", &GameConfig::default()));
+        assert!(is_valid_input("resign", &GameConfig::default()));
+        assert!(is_valid_input("draw", &GameConfig::default()));
+        assert!(is_valid_input("accept", &GameConfig::default()));
+    }
+
+    #[test]
+    fn test_accept_valid_input_save_load() {
+        assert!(is_valid_input("save game.txt", &GameConfig::default()));
+        assert!(is_valid_input("load game.txt", &GameConfig::default()));
+        assert!(!is_valid_input("save", &GameConfig::default()));
+        assert!(!is_valid_input("delete game.txt", &GameConfig::default()));
+    }
+
+    #[test]
+    fn test_reject_invalid_input() {
+        assert!(!is_valid_input("x", &GameConfig::default()));
+    }
+
+    #[test]
+    fn test_input_limited_by_board_width() {
+        assert!(!is_valid_input("8", &GameConfig::default()));
+        assert!(!is_valid_input("0", &GameConfig::default()));
+        assert!(is_valid_input("10", &GameConfig::new(10, 8, 4).unwrap()));
+    }
+
+    #[test]
+    fn test_parse_default_options() {
+        assert_eq!(parse_options(&[]).unwrap().config, GameConfig::default());
+    }
+
+    #[test]
+    fn test_parse_board_options() {
+        let options = parse_options(&args(&["--width", "9", "--height", "7", "--win", "5"]));
+        assert_eq!(options.unwrap().config, GameConfig::new(9, 7, 5).unwrap());
+    }
+
+    #[test]
+    fn test_parse_load_option() {
+        let options = parse_options(&args(&["--load", "game.txt"])).unwrap();
+        assert_eq!(options.load, Some(String::from("game.txt")));
+    }
+
+    #[test]
+    fn test_parse_record_options() {
+        let options = parse_options(&args(&["--record", "games.txt", "--player2", "Bob"])).unwrap();
+        assert_eq!(options.record, Some(String::from("games.txt")));
+        assert_eq!(options.player_names, [String::from("Player 1"), String::from("Bob")]);
+    }
+
+    #[test]
+    fn test_parse_clock_option() {
+        assert_eq!(parse_options(&[]).unwrap().time_control, None);
+        let options = parse_options(&args(&["--clock", "1/20"])).unwrap();
+        assert_eq!(options.time_control, Some(TimeControl::PerMove(Duration::from_secs(20))));
+        assert!(parse_options(&args(&["--clock", "5 minutes"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_default_has_no_computer() {
+        assert_eq!(parse_options(&[]).unwrap().computer, None);
+    }
+
+    #[test]
+    fn test_parse_computer_options() {
+        let options = parse_options(&args(&["--opponent", "computer"])).unwrap();
+        assert_eq!(options.computer,
+                   Some(Computer { player: Player::Two, ai: Ai::Negamax(NegamaxPlayer::new(7)) }));
+        let options = parse_options(&args(&["--opponent", "computer", "--first", "computer",
+                                            "--depth", "3"])).unwrap();
+        assert_eq!(options.computer,
+                   Some(Computer { player: Player::One, ai: Ai::Negamax(NegamaxPlayer::new(3)) }));
+        let options = parse_options(&args(&["--opponent", "mcts", "--think-time", "500"])).unwrap();
+        let ai = Ai::Mcts(MctsPlayer::new(Budget::Time(Duration::from_millis(500))));
+        assert_eq!(options.computer, Some(Computer { player: Player::Two, ai }));
+        let options = parse_options(&args(&["--opponent", "perfect"])).unwrap();
+        assert_eq!(options.computer, Some(Computer { player: Player::Two, ai: Ai::Perfect }));
+        assert!(parse_options(&args(&["--opponent", "robot"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_difficulty_option() {
+        let options = parse_options(&args(&["--difficulty", "easy"])).unwrap();
+        let ai = Ai::Level(Difficulty::Easy);
+        assert_eq!(options.computer, Some(Computer { player: Player::Two, ai }));
+        let options = parse_options(&args(&["--difficulty", "Expert", "--first", "computer"]));
+        let ai = Ai::Level(Difficulty::Expert);
+        assert_eq!(options.unwrap().computer, Some(Computer { player: Player::One, ai }));
+        assert!(parse_options(&args(&["--difficulty", "impossible"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_engine_option() {
+        let options = parse_options(&args(&["--engine", "./engine --fast", "--depth", "5"]));
+        let command = vec![String::from("./engine"), String::from("--fast")];
+        let ai = Ai::External { command, limits: Limits { depth: Some(5), movetime: None } };
+        assert_eq!(options.unwrap().computer, Some(Computer { player: Player::Two, ai }));
+        let options = parse_options(&args(&["--engine", "engine", "--think-time", "250"]));
+        let limits = Limits { depth: None, movetime: Some(Duration::from_millis(250)) };
+        let ai = Ai::External { command: vec![String::from("engine")], limits };
+        assert_eq!(options.unwrap().computer, Some(Computer { player: Player::Two, ai }));
+        assert!(parse_options(&args(&["--engine", " "])).is_err());
+    }
+
+    #[test]
+    fn test_parse_replay() {
+        assert_eq!(parse_replay("y", false), Some(Replay::Again));
+        assert_eq!(parse_replay("No", false), Some(Replay::Stop));
+        assert_eq!(parse_replay("hard", false), None);
+        assert_eq!(parse_replay("hard", true), Some(Replay::AgainAt(Difficulty::Hard)));
+        assert_eq!(parse_replay("maybe", true), None);
+    }
+
+    #[test]
+    fn test_parse_no_hints_option() {
+        assert!(parse_options(&[]).unwrap().hints);
+        let options = parse_options(&args(&["--no-hints", "--width", "8"])).unwrap();
+        assert!(!options.hints);
+        assert_eq!(options.config.width(), 8);
+    }
+
+    #[test]
+    fn test_describe_hint() {
+        let hint = Hint { column: 2, reason: HintReason::BlocksWin };
+        assert_eq!(describe_hint(hint), "Hint: play column 3 (blocks a win).");
+    }
+
+    #[test]
+    fn test_parse_invalid_options() {
+        assert!(parse_options(&args(&["--width"])).is_err());
+        assert!(parse_options(&args(&["--colour", "red"])).is_err());
+        assert!(parse_options(&args(&["--win", "9"])).is_err());
+        let conflicting = [
+            ["--opponent", "perfect", "--engine", "engine"],
+            ["--opponent", "mcts", "--difficulty", "hard"],
+            ["--difficulty", "hard", "--engine", "engine"],
+        ];
+        for options in conflicting {
+            assert!(parse_options(&args(&options)).is_err(), "{options:?}");
+        }
+    }
+
+    #[test]
+    fn test_solve_command() {
+        assert_eq!(solve_command(&args(&["121212"])).unwrap(),
+                   "Player 1 to move wins in 1 move with perfect play. Best move: column 1.");
+        assert_eq!(solve_command(&args(&["77777766666612121"])).unwrap(),
+                   "Player 2 to move draws with perfect play. Best move: column 1.");
+        assert!(solve_command(&args(&["1212121"])).is_err());
+        assert!(solve_command(&args(&["18"])).is_err());
+    }
+
+    #[test]
+    fn test_play_with_move_sources() {
+        let mut game = ConnectFourGame::default();
+        let mut sources: [Box<dyn MoveSource>; 2] = [
+            Box::new(ScriptedSource::new(&[0, 0, 0, 0])),
+            Box::new(ScriptedSource::new(&[1, 1, 1])),
+        ];
+        let mut renderer = TextRenderer::new(Vec::new());
+        play(&mut game, &mut sources, &mut renderer, None);
+        assert_eq!(game.state(), GameState::Win(Player::One));
+        let output = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(output.ends_with("Player 1 wins.\nMoves: 1212121\n"), "{output}");
+    }
+
+    #[test]
+    fn test_play_stops_when_source_quits() {
+        let mut game = ConnectFourGame::default();
+        let mut sources: [Box<dyn MoveSource>; 2] =
+                [Box::new(ScriptedSource::new(&[3, 3])), Box::new(NegamaxPlayer::new(2))];
+        play(&mut game, &mut sources, &mut TextRenderer::new(std::io::sink()), None);
+        assert_eq!(game.history().len(), 4);
+        assert_eq!(game.state(), GameState::Aborted);
+    }
+
+    // A move source that makes the given moves, then quits.
+    struct Commands(Vec<Move>);
+
+    impl MoveSource for Commands {
+        fn choose_move(&mut self, _view: &GameView) -> Move {
+            return if self.0.is_empty() { Move::Quit } else { self.0.remove(0) };
+        }
+
+        fn is_interactive(&self) -> bool {
+            return true;
+        }
+    }
+
+    #[test]
+    fn test_play_resign() {
+        let mut game = ConnectFourGame::default();
+        let mut sources: [Box<dyn MoveSource>; 2] = [
+            Box::new(ScriptedSource::new(&[3])),
+            Box::new(Commands(vec![Move::Resign])),
+        ];
+        let mut renderer = TextRenderer::new(Vec::new());
+        play(&mut game, &mut sources, &mut renderer, None);
+        assert_eq!(game.state(), GameState::Resigned(Player::Two));
+        let output = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(output.ends_with("Player 2 resigns. Player 1 wins.\nMoves: 4\n"), "{output}");
+    }
+
+    #[test]
+    fn test_play_draw_by_agreement() {
+        let mut game = ConnectFourGame::default();
+        let mut sources: [Box<dyn MoveSource>; 2] = [
+            Box::new(Commands(vec![Move::AcceptDraw, Move::OfferDraw, Move::Play(3)])),
+            Box::new(Commands(vec![Move::AcceptDraw])),
+        ];
+        let mut renderer = TextRenderer::new(Vec::new());
+        play(&mut game, &mut sources, &mut renderer, None);
+        assert_eq!(game.state(), GameState::DrawAgreed);
+        let output = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(output.contains("There's no draw offer to accept."), "{output}");
+        assert!(output.contains("Player 1 offers a draw."), "{output}");
+        assert!(output.ends_with("Draw agreed.\nMoves: 4\n"), "{output}");
+    }
+
+    #[test]
+    fn test_computer_declines_draw() {
+        let mut game = ConnectFourGame::default();
+        let mut sources: [Box<dyn MoveSource>; 2] = [
+            Box::new(Commands(vec![Move::OfferDraw])),
+            Box::new(ScriptedSource::new(&[])),
+        ];
+        let mut renderer = TextRenderer::new(Vec::new());
+        play(&mut game, &mut sources, &mut renderer, None);
+        assert_eq!(game.state(), GameState::Aborted);
+        let output = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(output.contains("The computer declines the draw."), "{output}");
+        assert!(output.ends_with("Game aborted.\nMoves: \n"), "{output}");
+    }
+
+    #[test]
+    fn test_play_loses_on_time() {
+        let mut game = ConnectFourGame::default();
+        let mut sources: [Box<dyn MoveSource>; 2] = [
+            Box::new(ScriptedSource::new(&[3, 3])),
+            Box::new(MctsPlayer::with_seed(Budget::Time(Duration::from_secs(60)), 1)),
+        ];
+        let mut clock = Clock::new(TimeControl::PerMove(Duration::from_millis(50)));
+        let mut renderer = TextRenderer::new(Vec::new());
+        play(&mut game, &mut sources, &mut renderer, Some(&mut clock));
+        // The computer cuts its thinking short to stay within the clock, so the game goes on
+        // until the script runs out and quits.
+        assert_eq!(game.history().len(), 4);
+        assert_eq!(game.state(), GameState::Aborted);
+        let mut clock = Clock::new(TimeControl::PerMove(Duration::ZERO));
+        let mut game = ConnectFourGame::default();
+        play(&mut game, &mut sources, &mut renderer, Some(&mut clock));
+        assert_eq!(game.state(), GameState::OutOfTime(Player::One));
+        let output = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(output.contains("Time left: Player 1 0:00.0, Player 2 0:00.0"), "{output}");
+        assert!(output.ends_with("Player 1 ran out of time. Player 2 wins.\nMoves: \n"),
+                "{output}");
+    }
+
+    #[test]
+    fn test_network_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let joiner = std::thread::spawn(move || {
+            let stream = TcpStream::connect(address).unwrap();
+            let mut renderer = TextRenderer::new(Vec::new());
+            network::join(stream, &mut ScriptedSource::new(&[1, 1, 1]), &mut renderer).unwrap();
+            return String::from_utf8(renderer.into_inner()).unwrap();
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let remote = RemotePlayer::new(stream.try_clone().unwrap(), GameConfig::default(),
+                                       Player::Two).unwrap();
+        let mut sources: [Box<dyn MoveSource>; 2] =
+                [Box::new(ScriptedSource::new(&[0, 0, 0, 0])), Box::new(remote)];
+        let mut renderer = RemoteRenderer::new(TextRenderer::new(std::io::sink()), stream,
+                                               Player::Two, GameConfig::default());
+        let mut game = ConnectFourGame::default();
+        play(&mut game, &mut sources, &mut renderer, None);
+        assert_eq!(game.state(), GameState::Win(Player::One));
+        let output = joiner.join().unwrap();
+        assert!(output.ends_with("Player 1 wins.\nMoves: 1212121\n"), "{output}");
+    }
+
+    #[test]
+    fn test_move_sources() {
+        let computer = Computer { player: Player::One, ai: Ai::Level(Difficulty::Easy) };
+        let sources = move_sources(Some(&computer), GameConfig::default(), true).unwrap();
+        assert!(!sources[0].is_interactive());
+        assert!(sources[1].is_interactive());
+    }
+
+    #[test]
+    fn test_convert_input_to_column() {
+        for x in 1..7 {
+            assert_eq!(convert_input_to_column(x.to_string()), x - 1);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use crate::game::Player;
+use crate::negamax::move_order;
+use crate::position::Position;
+use crate::rng::Rng;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+
+
+const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+
+// How long the player may think about a move.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Budget {
+    Iterations(usize),
+    Time(Duration),
+}
+
+
+// A computer player that uses Monte Carlo tree search with the UCT selection rule. Each
+// iteration walks down the tree of explored moves, favouring moves that have done well while
+// still trying the less explored ones, adds one new move, and then plays the game out to the
+// end. The move explored the most is played. Unlike `NegamaxPlayer` it needs no evaluation
+// function, which makes it a good fit for large custom boards.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct MctsPlayer {
+    budget: Budget,
+    exploration: f64,  // How much to favour less explored moves over ones that have done well.
+    seed: Option<u64>,  // None to seed every search from the clock.
+}
+
+
+// A node of the search tree: a position reached by a move, with the results of the playouts
+// that passed through it.
+struct Node {
+    col: usize,  // The move that reached the node.
+    player: Player,  // The player who made the move.
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<usize>,  // The moves from this node that don't have a child yet.
+    visits: u32,
+    score: f64,  // The results for `player`: 1 for every win and 0.5 for every draw.
+    terminal: bool,  // Whether the game is over after the move.
+    winner: Option<Player>,  // The winner if the game is over, or None for a draw.
+}
+
+
+impl MctsPlayer {
+    pub fn new(budget: Budget) -> Self {
+        return MctsPlayer { budget, exploration: DEFAULT_EXPLORATION, seed: None };
+    }
+
+
+    // Create a player whose playouts always use the given seed, so its moves are repeatable.
+    pub fn with_seed(budget: Budget, seed: u64) -> Self {
+        return MctsPlayer { budget, exploration: DEFAULT_EXPLORATION, seed: Some(seed) };
+    }
+
+
+    pub fn budget(&self) -> Budget {
+        return self.budget;
+    }
+
+
+    // Return a copy of the player that thinks for the given budget instead.
+    pub fn with_budget(&self, budget: Budget) -> Self {
+        return MctsPlayer { budget, ..self.clone() };
+    }
+
+
+    // Return the best column for the player to move, or None if the board is full.
+    pub fn best_move(&self, position: &Position) -> Option<usize> {
+        return self.search(position).map(|(col, _)| col);
+    }
+
+
+    // Return the best column for the player to move with the fraction of its playouts the
+    // player won (counting draws as half), or None if the board is full.
+    pub fn search(&self, position: &Position) -> Option<(usize, f64)> {
+        let width: usize = position.config().width();
+        if let Some(col) = (0..width).find(|col| position.is_winning_move(*col)) {
+            return Some((col, 1.0));
+        }
+        let mut rng: Rng = match self.seed {
+            Some(seed) => Rng::new(seed),
+            None => Rng::from_time(),
+        };
+        let mut tree: Vec<Node> = vec![Node {
+            col: 0,
+            player: position.to_move().other(),
+            parent: None,
+            children: Vec::new(),
+            untried: playable_columns(position),
+            visits: 0,
+            score: 0.0,
+            terminal: false,
+            winner: None,
+        }];
+        if tree[0].untried.is_empty() {
+            return None;
+        }
+        let start: Instant = Instant::now();
+        let mut iterations: usize = 0;
+        while match self.budget {
+            Budget::Iterations(limit) => iterations < limit.max(1),
+            Budget::Time(limit) => iterations == 0 || start.elapsed() < limit,
+        } {
+            self.iterate(&mut tree, position, &mut rng);
+            iterations += 1;
+        }
+        let best: &Node = tree[0].children.iter()
+                .map(|child| &tree[*child])
+                .max_by_key(|child| child.visits)
+                .expect("the root has been expanded");
+        return Some((best.col, best.score / best.visits as f64));
+    }
+
+
+    // Run one iteration of the search: select, expand, play out and record the result.
+    fn iterate(&self, tree: &mut Vec<Node>, root: &Position, rng: &mut Rng) {
+        let mut position: Position = root.clone();
+        let mut node: usize = 0;
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            node = self.select_child(tree, node);
+            position.play(tree[node].col);
+        }
+        if !tree[node].terminal && !tree[node].untried.is_empty() {
+            let untried: &mut Vec<usize> = &mut tree[node].untried;
+            let col: usize = untried.swap_remove(rng.below(untried.len()));
+            let player: Player = position.to_move();
+            let won: bool = position.is_winning_move(col);
+            position.play(col);
+            let terminal: bool = won || position.is_full();
+            let untried: Vec<usize> =
+                    if terminal { Vec::new() } else { playable_columns(&position) };
+            tree.push(Node {
+                col,
+                player,
+                parent: Some(node),
+                children: Vec::new(),
+                untried,
+                visits: 0,
+                score: 0.0,
+                terminal,
+                winner: if won { Some(player) } else { None },
+            });
+            let child: usize = tree.len() - 1;
+            tree[node].children.push(child);
+            node = child;
+        }
+        let winner: Option<Player> =
+                if tree[node].terminal { tree[node].winner } else { playout(&mut position, rng) };
+        let mut current: Option<usize> = Some(node);
+        while let Some(index) = current {
+            let node: &mut Node = &mut tree[index];
+            node.visits += 1;
+            node.score += match winner {
+                Some(winner) if winner == node.player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+
+    // Return the child to explore next: the one with the best upper confidence bound on its
+    // score, which is high for children that have done well or been tried little.
+    fn select_child(&self, tree: &[Node], node: usize) -> usize {
+        let log_visits: f64 = (tree[node].visits as f64).ln();
+        let bound = |child: &usize| -> f64 {
+            let child: &Node = &tree[*child];
+            let visits: f64 = child.visits as f64;
+            return child.score / visits + self.exploration * (log_visits / visits).sqrt();
+        };
+        return *tree[node].children.iter()
+                .max_by(|a, b| bound(a).total_cmp(&bound(b)))
+                .expect("the node has children");
+    }
+}
+
+
+// Return the columns that can be played, in the usual center-first search order.
+fn playable_columns(position: &Position) -> Vec<usize> {
+    return move_order(position.config().width()).into_iter()
+            .filter(|col| position.can_play(*col))
+            .collect();
+}
+
+
+// Play the game out to the end and return the winner, or None for a draw. Moves are random,
+// except that a player always takes an immediate win and blocks an immediate loss, which makes
+// the results much closer to real play than purely random moves.
+fn playout(position: &mut Position, rng: &mut Rng) -> Option<Player> {
+    let width: usize = position.config().width();
+    loop {
+        if position.is_full() {
+            return None;
+        }
+        let player: Player = position.to_move();
+        if (0..width).any(|col| position.is_winning_move(col)) {
+            return Some(player);
+        }
+        let cols: Vec<usize> = (0..width).filter(|col| position.can_play(*col)).collect();
+        let block: Option<usize> = cols.iter().copied().find(|col| {
+            position.place(player.other(), *col);
+            let loses: bool = position.has_won(player.other());
+            position.undo(*col);
+            loses
+        });
+        position.play(block.unwrap_or_else(|| cols[rng.below(cols.len())]));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameConfig;
+    use crate::notation;
+    use crate::notation::test_positions::position;
+
+    #[test]
+    fn test_takes_immediate_win() {
+        let player = MctsPlayer::with_seed(Budget::Iterations(100), 1);
+        assert_eq!(player.best_move(&position("121212")), Some(0));
+    }
+
+    #[test]
+    fn test_blocks_immediate_loss() {
+        let player = MctsPlayer::with_seed(Budget::Iterations(2000), 1);
+        assert_eq!(player.best_move(&position("12121")), Some(0));
+    }
+
+    #[test]
+    fn test_blocks_open_three() {
+        let player = MctsPlayer::with_seed(Budget::Iterations(5000), 1);
+        let best = player.best_move(&position("445")).unwrap();
+        assert!(best == 2 || best == 5);
+    }
+
+    #[test]
+    fn test_same_seed_same_move() {
+        let player = MctsPlayer::with_seed(Budget::Iterations(500), 9);
+        assert_eq!(player.search(&position("4453")), player.search(&position("4453")));
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let player = MctsPlayer::with_seed(Budget::Time(Duration::from_millis(50)), 1);
+        let start = Instant::now();
+        assert!(player.best_move(&position("")).is_some());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_large_board() {
+        // Player 1 has three in a row along the bottom and needs five. Unless player 2 takes a
+        // cell next to the three, player 1 gets four with both ends open.
+        let config = GameConfig::new(9, 8, 5).unwrap();
+        let game = notation::parse(config, "41516").unwrap();
+        let player = MctsPlayer::with_seed(Budget::Iterations(5000), 1);
+        let best = player.best_move(game.position()).unwrap();
+        assert!(best == 2 || best == 6, "{best}");
+    }
+}
@@ -0,0 +1,289 @@
+// Where the moves of each side come from.
+//
+// The game loop asks a `MoveSource` for every move, so people at the keyboard, computer players,
+// scripts and remote players are interchangeable: the loop doesn't know or care which is which.
+// The computer players in this crate are all move sources.
+
+use crate::difficulty::DifficultyPlayer;
+use crate::game::ConnectFourGame;
+use crate::game::GameConfig;
+use crate::game::Player;
+use crate::mcts::Budget;
+use crate::mcts::MctsPlayer;
+use crate::negamax::NegamaxPlayer;
+use crate::position::Position;
+use crate::solver::MOVE_TIME_LIMIT;
+use crate::solver::Solver;
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+
+const FALLBACK_DEPTH: usize = 7;
+
+
+// What a move source decides to do on its turn.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Move {
+    Play(usize),  // Drop a token into the given column (0-indexed).
+    Undo,
+    Redo,
+    Load(String),  // Replace the game with the one saved at the given path.
+    Resign,  // Give up, so the other player wins.
+    OfferDraw,  // Offer the other player a draw. The player still has to move.
+    AcceptDraw,  // Accept the draw the other player has offered.
+    Quit,  // Abandon the game with no result.
+}
+
+
+// A read-only view of the game for a move source to choose its move from.
+pub struct GameView<'a> {
+    game: &'a ConnectFourGame,
+    think_time: Option<Duration>,  // How long the player may think, when playing on a clock.
+    deadline: Option<Instant>,  // When the player's clock runs out, when playing on a clock.
+}
+
+
+impl<'a> GameView<'a> {
+    pub fn new(game: &'a ConnectFourGame) -> Self {
+        return GameView { game, think_time: None, deadline: None };
+    }
+
+
+    // Create a view for a player on a clock, who should choose their move in the given think
+    // time and loses on time after the given time left.
+    pub fn with_clock(game: &'a ConnectFourGame, think_time: Duration, time_left: Duration)
+            -> Self {
+        let deadline: Instant = Instant::now() + time_left;
+        return GameView { game, think_time: Some(think_time), deadline: Some(deadline) };
+    }
+
+
+    pub fn game(&self) -> &'a ConnectFourGame {
+        return self.game;
+    }
+
+
+    pub fn position(&self) -> &'a Position {
+        return self.game.position();
+    }
+
+
+    pub fn config(&self) -> GameConfig {
+        return self.game.config();
+    }
+
+
+    // Return the player whose move is being chosen.
+    pub fn player(&self) -> Player {
+        return self.game.active_player();
+    }
+
+
+    // Return how long the player may think about their move without running short on time, or
+    // None if there's no clock.
+    pub fn think_time(&self) -> Option<Duration> {
+        return self.think_time;
+    }
+
+
+    // Return when the player's clock runs out, or None if there's no clock. A move source that
+    // waits for a person or another program to answer stops waiting then, and whatever it
+    // returns is ignored, since the player has lost on time.
+    pub fn deadline(&self) -> Option<Instant> {
+        return self.deadline;
+    }
+}
+
+
+pub trait MoveSource {
+    // Choose what to do on the turn of the player to move. The game isn't over.
+    fn choose_move(&mut self, view: &GameView) -> Move;
+
+
+    // Return whether a person chooses the moves. Undo and redo skip over the turns of sources
+    // that aren't interactive, so that a person undoing a move against the computer gets their
+    // own turn back.
+    fn is_interactive(&self) -> bool {
+        return false;
+    }
+
+
+    // Decide whether to accept the draw offered by the player to move, for a source that isn't
+    // interactive and so won't be asked to move until the offer is settled. Sources that don't
+    // judge positions decline.
+    fn accept_draw(&mut self, _view: &GameView) -> bool {
+        return false;
+    }
+}
+
+
+// A move source that plays a fixed list of columns and then quits, for scripted games and
+// tests.
+pub struct ScriptedSource {
+    moves: VecDeque<usize>,
+}
+
+
+impl ScriptedSource {
+    pub fn new(moves: &[usize]) -> Self {
+        return ScriptedSource { moves: moves.iter().copied().collect() };
+    }
+}
+
+
+impl MoveSource for ScriptedSource {
+    fn choose_move(&mut self, _view: &GameView) -> Move {
+        return self.moves.pop_front().map_or(Move::Quit, Move::Play);
+    }
+}
+
+
+// On a clock, the player searches less deeply if it has to.
+impl MoveSource for NegamaxPlayer {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        let col: Option<usize> = match view.think_time() {
+            Some(think_time) => self.best_move_within(view.position(), think_time),
+            None => self.best_move(view.position()),
+        };
+        return Move::Play(col.expect("the game isn't over"));
+    }
+
+
+    // A draw is accepted when the player offering it is ahead.
+    fn accept_draw(&mut self, view: &GameView) -> bool {
+        return self.search(view.position()).is_some_and(|(_, score)| score > 0);
+    }
+}
+
+
+// A player thinking for a length of time thinks for no longer than the clock allows.
+impl MoveSource for MctsPlayer {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        let player: MctsPlayer = match (self.budget(), view.think_time()) {
+            (Budget::Time(budget), Some(think_time)) if think_time < budget =>
+                    self.with_budget(Budget::Time(think_time)),
+            _ => self.clone(),
+        };
+        return Move::Play(player.best_move(view.position()).expect("the game isn't over"));
+    }
+}
+
+
+impl MoveSource for DifficultyPlayer {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        let col: Option<usize> = match view.think_time() {
+            Some(think_time) => self.choose_move_within(view.position(), think_time),
+            None => self.choose_move(view.position()),
+        };
+        return Move::Play(col.expect("the game isn't over"));
+    }
+}
+
+
+// The solver plays perfectly on the standard board once it can solve the position in time. Before
+// that, and on other boards, which it can't solve, it searches a fixed number of moves ahead
+// instead. On a clock, it spends up to half of its thinking time trying to solve the position
+// and the rest on the fixed-depth search, which searches less deeply if it has to.
+impl MoveSource for Solver {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        let start: Instant = Instant::now();
+        let limit: Duration =
+                view.think_time().map_or(MOVE_TIME_LIMIT, |t| (t / 2).min(MOVE_TIME_LIMIT));
+        if let Ok(col) = self.best_move_within(view.position(), limit) {
+            return Move::Play(col);
+        }
+        let negamax: NegamaxPlayer = NegamaxPlayer::new(FALLBACK_DEPTH);
+        let col: Option<usize> = match view.think_time() {
+            Some(think_time) => {
+                let time_left: Duration = think_time.saturating_sub(start.elapsed());
+                negamax.best_move_within(view.position(), time_left)
+            }
+            None => negamax.best_move(view.position()),
+        };
+        return Move::Play(col.expect("the game isn't over"));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::hint;
+    use crate::notation;
+    use crate::notation::test_positions::full_board;
+    use crate::transposition::Replacement;
+
+    #[test]
+    fn test_scripted_source() {
+        let game = ConnectFourGame::default();
+        let mut source = ScriptedSource::new(&[3, 4]);
+        assert_eq!(source.choose_move(&GameView::new(&game)), Move::Play(3));
+        assert_eq!(source.choose_move(&GameView::new(&game)), Move::Play(4));
+        assert_eq!(source.choose_move(&GameView::new(&game)), Move::Quit);
+        assert!(!source.is_interactive());
+    }
+
+    #[test]
+    fn test_computer_sources_take_win() {
+        let game = notation::parse(GameConfig::default(), "121212").unwrap();
+        let view = GameView::new(&game);
+        let mut sources: Vec<Box<dyn MoveSource>> = vec![
+            Box::new(NegamaxPlayer::new(2)),
+            Box::new(MctsPlayer::with_seed(Budget::Iterations(100), 1)),
+            Box::new(DifficultyPlayer::with_seed(Difficulty::Hard, 1)),
+            Box::new(Solver::with_table(1 << 16, Replacement::Always)),
+        ];
+        for source in &mut sources {
+            assert_eq!(source.choose_move(&view), Move::Play(0));
+        }
+    }
+
+    #[test]
+    fn test_negamax_accepts_draw_when_behind() {
+        let mut negamax = NegamaxPlayer::new(4);
+        // Player 1 can win at once, so player 2 is glad of a draw.
+        let game = notation::parse(GameConfig::default(), "121212").unwrap();
+        assert!(negamax.accept_draw(&GameView::new(&game)));
+        assert!(!negamax.accept_draw(&GameView::new(&ConnectFourGame::default())));
+        assert!(!ScriptedSource::new(&[]).accept_draw(&GameView::new(&game)));
+    }
+
+    #[test]
+    fn test_full_board_has_no_move() {
+        let full = full_board();
+        assert_eq!(NegamaxPlayer::new(2).best_move(&full), None);
+        assert_eq!(MctsPlayer::new(Budget::Iterations(10)).best_move(&full), None);
+        assert_eq!(hint::suggest(&full), None);
+    }
+
+    #[test]
+    fn test_solver_source_on_custom_board() {
+        let game = notation::parse(GameConfig::new(8, 7, 4).unwrap(), "12121").unwrap();
+        let mut solver = Solver::with_table(1 << 16, Replacement::Always);
+        assert_eq!(solver.choose_move(&GameView::new(&game)), Move::Play(0));
+    }
+
+    #[test]
+    fn test_solver_source_answers_in_opening() {
+        // Solving this position takes minutes, so the solver searches a few moves ahead instead.
+        let game = notation::parse(GameConfig::default(), "4").unwrap();
+        let mut solver = Solver::with_table(1 << 16, Replacement::Always);
+        let view = GameView::with_clock(&game, Duration::from_millis(50), Duration::from_secs(1));
+        let start = Instant::now();
+        assert!(matches!(solver.choose_move(&view), Move::Play(_)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_view() {
+        let game = notation::parse(GameConfig::default(), "45").unwrap();
+        let view = GameView::new(&game);
+        assert_eq!(view.player(), Player::One);
+        assert_eq!(view.position().moves(), 2);
+        assert_eq!(view.config(), GameConfig::default());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::position::Bitboard;
+use crate::position::Position;
+use crate::transposition::Bound;
+use crate::transposition::Entry;
+use crate::transposition::Replacement;
+use crate::transposition::TranspositionTable;
+use std::cmp::max;
+use std::cmp::min;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+
+
+pub const BRANCHING_FACTOR: u32 = 4;  // Roughly how much longer each search depth takes.
+const DEFAULT_TABLE_MEMORY: usize = 16 << 20;
+const INFINITY: i32 = i32::MAX / 2;  // Leaves room to negate without overflowing.
+// The most a line's weight in `evaluate` is shifted by. Lines with 5 or more tokens all count
+// the same, which keeps any board's score well below `WIN_SCORE` and the shifts in range.
+const MAX_LINE_WEIGHT_SHIFT: u32 = 8;
+const WIN_SCORE: i32 = 1_000_000;  // Far above any heuristic score.
+
+
+// A computer player that looks a fixed number of moves (plies) ahead using negamax search with
+// alpha-beta pruning. Positions at the search horizon are scored with a heuristic that rewards
+// lines the player could still complete. Each search caches what it learns about positions in
+// a transposition table, so positions reached by different move orders are only searched once.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct NegamaxPlayer {
+    depth: usize,
+    table_memory: usize,  // The memory budget of the transposition table, in bytes.
+    replacement: Replacement,
+}
+
+
+impl NegamaxPlayer {
+    pub fn new(depth: usize) -> Self {
+        return NegamaxPlayer::with_table(depth, DEFAULT_TABLE_MEMORY, Replacement::DepthPreferred);
+    }
+
+
+    // Create a player whose transposition table uses about the given number of bytes and the
+    // given replacement policy.
+    pub fn with_table(depth: usize, table_memory: usize, replacement: Replacement) -> Self {
+        return NegamaxPlayer { depth: max(depth, 1), table_memory, replacement };
+    }
+
+
+    pub fn depth(&self) -> usize {
+        return self.depth;
+    }
+
+
+    // Return the best column for the player to move, or None if the board is full.
+    pub fn best_move(&self, position: &Position) -> Option<usize> {
+        return self.search(position).map(|(col, _)| col);
+    }
+
+
+    // Return the best column as `best_move` does, but search one move ahead first and then
+    // deeper, up to the player's depth, only while the next depth will likely finish within the
+    // given time.
+    pub fn best_move_within(&self, position: &Position, time: Duration) -> Option<usize> {
+        let start: Instant = Instant::now();
+        let mut best: Option<usize> = None;
+        for depth in 1..=self.depth {
+            let depth_start: Instant = Instant::now();
+            best = NegamaxPlayer { depth, ..self.clone() }.best_move(position);
+            if start.elapsed() + depth_start.elapsed() * BRANCHING_FACTOR >= time {
+                break;
+            }
+        }
+        return best;
+    }
+
+
+    // Return the best column for the player to move with its score, or None if the board is
+    // full. Positive scores favour the player to move; wins score above `WIN_SCORE / 2`, and
+    // sooner wins score higher.
+    pub fn search(&self, position: &Position) -> Option<(usize, i32)> {
+        let mut position: Position = position.clone();
+        let lines: Vec<Bitboard> = position.lines();
+        let order: Vec<usize> = move_order(position.config().width());
+        let mut table: TranspositionTable =
+                TranspositionTable::new(self.table_memory, self.replacement);
+        let mut best: Option<(usize, i32)> = None;
+        let mut alpha: i32 = -INFINITY;
+        for col in &order {
+            if !position.can_play(*col) {
+                continue;
+            }
+            let score: i32 = if position.is_winning_move(*col) {
+                win_score(&position)
+            } else {
+                position.play(*col);
+                let score: i32 = -negamax(&mut position, &lines, &order, &mut table,
+                                          self.depth - 1, -INFINITY, -alpha);
+                position.undo(*col);
+                score
+            };
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((*col, score));
+            }
+            alpha = max(alpha, score);
+        }
+        return best;
+    }
+}
+
+
+// Return the columns in the order to search them: the center first, since center columns take
+// part in the most lines, then alternating outwards, left before right.
+pub fn move_order(width: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..width).collect();
+    order.sort_by_key(|col| (2 * col).abs_diff(width - 1));
+    return order;
+}
+
+
+// Return the score of the position for the player to move, searching the given number of moves
+// ahead. Scores outside the (alpha, beta) window are only bounds, and are stored in the table
+// as such.
+fn negamax(position: &mut Position, lines: &[Bitboard], order: &[usize],
+           table: &mut TranspositionTable, depth: usize, mut alpha: i32, mut beta: i32) -> i32 {
+    if position.is_full() {
+        return 0;
+    }
+    if order.iter().any(|col| position.is_winning_move(*col)) {
+        return win_score(position);
+    }
+    if depth == 0 {
+        return evaluate(position, lines);
+    }
+    let original_alpha: i32 = alpha;
+    let entry: Option<Entry> = table.get(position.hash());
+    if let Some(entry) = entry.filter(|entry| entry.depth as usize >= depth) {
+        match entry.bound {
+            Bound::Exact => return entry.score,
+            Bound::Lower => alpha = max(alpha, entry.score),
+            Bound::Upper => beta = min(beta, entry.score),
+        }
+        if alpha >= beta {
+            return entry.score;
+        }
+    }
+
+    // Try the best move from an earlier search of this position first.
+    let first: Option<usize> = entry.and_then(|entry| entry.best_move).map(usize::from);
+    let mut best: i32 = -INFINITY;
+    let mut best_move: Option<usize> = None;
+    for col in first.iter().chain(order.iter().filter(|col| Some(**col) != first)) {
+        if !position.can_play(*col) {
+            continue;
+        }
+        position.play(*col);
+        let score: i32 = -negamax(position, lines, order, table, depth - 1, -beta, -alpha);
+        position.undo(*col);
+        if score > best {
+            best = score;
+            best_move = Some(*col);
+        }
+        alpha = max(alpha, score);
+        if alpha >= beta {
+            break;  // The opponent won't allow this position.
+        }
+    }
+    let bound: Bound = if best <= original_alpha {
+        Bound::Upper
+    } else if best >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    table.store(Entry {
+        hash: position.hash(),
+        depth: depth as u16,
+        score: best,
+        bound,
+        best_move: best_move.map(|col| col as u8),
+    });
+    return best;
+}
+
+
+// Return the score of winning with the next move. Wins that take fewer moves score higher.
+fn win_score(position: &Position) -> i32 {
+    return WIN_SCORE - position.moves() as i32;
+}
+
+
+// Score a position for the player to move without searching. Every line that only one player
+// has tokens in counts for that player, and lines closer to completion count for much more.
+pub fn evaluate(position: &Position, lines: &[Bitboard]) -> i32 {
+    let own: Bitboard = position.mask(position.to_move());
+    let other: Bitboard = position.mask(position.to_move().other());
+    let mut score: i32 = 0;
+    for line in lines {
+        let own_count: u32 = (line & own).count_ones();
+        let other_count: u32 = (line & other).count_ones();
+        if other_count == 0 && own_count > 0 {
+            score += 1 << (2 * (own_count - 1)).min(MAX_LINE_WEIGHT_SHIFT);
+        } else if own_count == 0 && other_count > 0 {
+            score -= 1 << (2 * (other_count - 1)).min(MAX_LINE_WEIGHT_SHIFT);
+        }
+    }
+    return score;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameConfig;
+    use crate::notation;
+    use crate::notation::test_positions::position;
+
+    #[test]
+    fn test_move_order() {
+        assert_eq!(move_order(7), vec![3, 2, 4, 1, 5, 0, 6]);
+        assert_eq!(move_order(8), vec![3, 4, 2, 5, 1, 6, 0, 7]);
+    }
+
+    #[test]
+    fn test_opens_in_center() {
+        assert_eq!(NegamaxPlayer::new(4).best_move(&position("")), Some(3));
+    }
+
+    #[test]
+    fn test_takes_immediate_win() {
+        // Player 1 can win in column 1 vertically.
+        assert_eq!(NegamaxPlayer::new(4).best_move(&position("121212")), Some(0));
+    }
+
+    #[test]
+    fn test_blocks_immediate_loss() {
+        // Player 2 must block the vertical line in column 1.
+        assert_eq!(NegamaxPlayer::new(4).best_move(&position("12121")), Some(0));
+    }
+
+    #[test]
+    fn test_blocks_open_three() {
+        // Player 1 threatens to get three in a row open at both ends along the bottom; player 2
+        // must take one end now or lose.
+        let best = NegamaxPlayer::new(6).best_move(&position("445")).unwrap();
+        assert!(best == 2 || best == 5);
+    }
+
+    #[test]
+    fn test_best_move_within_time() {
+        // With no time to spare, the player still looks one move ahead.
+        let player = NegamaxPlayer::new(12);
+        assert_eq!(player.best_move_within(&position("121212"), Duration::ZERO), Some(0));
+        let start = Instant::now();
+        assert!(player.best_move_within(&position(""), Duration::from_millis(50)).is_some());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_win_score_prefers_faster_win() {
+        let (_, score) = NegamaxPlayer::new(4).search(&position("121212")).unwrap();
+        assert_eq!(score, WIN_SCORE - 6);
+    }
+
+    #[test]
+    fn test_table_size_does_not_change_result() {
+        for moves in ["", "4453", "445", "3344552"] {
+            let tiny = NegamaxPlayer::with_table(6, 0, Replacement::Always);
+            let large = NegamaxPlayer::new(6);
+            assert_eq!(tiny.search(&position(moves)), large.search(&position(moves)), "{moves}");
+        }
+    }
+
+
+    #[test]
+    fn test_evaluate_symmetric() {
+        assert_eq!(evaluate(&position(""), &position("").lines()), 0);
+        let after_center = position("4");
+        // Player 2 is to move and player 1 holds the center.
+        assert!(evaluate(&after_center, &after_center.lines()) < 0);
+    }
+
+    #[test]
+    fn test_evaluate_long_win_length() {
+        // A column of 19 tokens lies in a line that needs 20.
+        let config = GameConfig::new(4, 24, 20).unwrap();
+        let game = notation::parse(config, &"12".repeat(19)).unwrap();
+        let position = game.position();
+        assert!(evaluate(position, &position.lines()).abs() < WIN_SCORE / 2);
+        assert!(NegamaxPlayer::new(2).best_move(position).is_some());
+    }
+}
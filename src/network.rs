@@ -0,0 +1,726 @@
+// Games between two people on different machines, over TCP.
+//
+// The host runs the game and the joining player sends it moves. The host checks every move and
+// sends the board back after each one, so the joining player's copy of the game is only ever
+// a view of the host's. Anyone else who connects watches the game as a spectator. Messages are
+// lines of text:
+//
+//   hello W H N P    Host to client on connecting: the board is W x H with N in a row to win,
+//                    and the client plays as player P (1 or 2).
+//   spectate W H N   Host to client on connecting: the client watches a game on a W x H board
+//                    with N in a row to win. The board so far follows, so a spectator who
+//                    connects late catches up, and spectators only ever get `board`, `clock`
+//                    and `gameover` messages.
+//   board [C ...]    Host to client: the moves played so far, as 1-indexed columns.
+//   clock T1 T2      Host to client: the milliseconds player 1 and player 2 have left, sent
+//                    after the board in a game played on a clock. The host keeps the clocks.
+//   drawoffer        Host to client: the other player offers a draw, which the client may
+//                    accept on this turn. A `yourturn` follows.
+//   yourturn         Host to client: send a move.
+//   error TEXT       Host to client: the move couldn't be played. A new `yourturn` follows.
+//   gameover R       Host to client: the game is over, and R is `win 1`, `win 2`, `tie`,
+//                    `time P` or `resign P` when player P ran out of time or resigned, `draw`
+//                    when the players agreed to a draw, or `abort` when the game was abandoned.
+//                    The final board comes just before it.
+//   move C           Client to host: play the 1-indexed column C.
+//   resign           Client to host: resign the game.
+//   draw             Client to host: offer a draw. A new `yourturn` follows.
+//   accept           Client to host: accept the draw offered.
+//   quit             Client to host: leave the game, which forfeits it like resigning.
+
+use crate::game::ConnectFourGame;
+use crate::game::GameConfig;
+use crate::game::GameState;
+use crate::game::MoveError;
+use crate::game::Player;
+use crate::move_source::GameView;
+use crate::move_source::Move;
+use crate::move_source::MoveSource;
+use crate::render;
+use crate::render::Renderer;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+
+
+const MIN_READ_TIMEOUT: Duration = Duration::from_millis(1);
+
+
+// A message between the host and the joining player.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Message {
+    Hello { config: GameConfig, player: Player },
+    Spectate { config: GameConfig },
+    Board(Vec<usize>),  // The moves played so far, as 0-indexed columns.
+    Clock([Duration; 2]),  // The time player 1 and player 2 have left.
+    DrawOffer,
+    YourTurn,
+    Error(String),
+    GameOver(GameState),
+    Move(usize),  // A 0-indexed column.
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    Quit,
+}
+
+
+// The joining player, seen from the host: a move source that asks the other machine for its
+// moves and turns down the ones that can't be played.
+pub struct RemotePlayer {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+
+// A renderer for the host that shows the game locally and keeps the boards of the joining
+// player and any spectators up to date.
+pub struct RemoteRenderer<R: Renderer> {
+    local: R,
+    writer: TcpStream,
+    remote: Player,  // The joining player.
+    spectators: Spectators,
+}
+
+
+// The people watching a game. They can be added from any thread while the game goes on, and
+// are sent everything they need to catch up when they're added.
+#[derive(Clone)]
+pub struct Spectators {
+    audience: Arc<Mutex<Audience>>,
+}
+
+
+struct Audience {
+    config: GameConfig,
+    moves: Vec<usize>,  // The moves played so far, as last sent.
+    times: Option<[Duration; 2]>,  // The clocks as last sent, in a game played on a clock.
+    result: Option<GameState>,  // How the game ended, once it has.
+    streams: Vec<TcpStream>,
+}
+
+
+impl Message {
+    // Parse a line of the protocol, or return None if it isn't a message.
+    pub fn parse(line: &str) -> Option<Message> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let numbers: Option<Vec<usize>> =
+                words.iter().skip(1).map(|word| word.parse::<usize>().ok()).collect();
+        return match (words.as_slice(), numbers.as_deref()) {
+            (["hello", ..], Some(&[width, height, win_length, player])) => Some(Message::Hello {
+                config: GameConfig::new(width, height, win_length).ok()?,
+                player: parse_player(player)?,
+            }),
+            (["board", ..], Some(moves)) if !moves.contains(&0) =>
+                    Some(Message::Board(moves.iter().map(|col| col - 1).collect())),
+            (["spectate", ..], Some(&[width, height, win_length])) => Some(Message::Spectate {
+                config: GameConfig::new(width, height, win_length).ok()?,
+            }),
+            (["clock", ..], Some(&[time1, time2])) => Some(Message::Clock(
+                    [Duration::from_millis(time1 as u64), Duration::from_millis(time2 as u64)])),
+            (["drawoffer"], _) => Some(Message::DrawOffer),
+            (["yourturn"], _) => Some(Message::YourTurn),
+            (["error", ..], _) =>
+                    Some(Message::Error(String::from(line.trim()["error".len()..].trim()))),
+            (["gameover", "tie"], _) => Some(Message::GameOver(GameState::Tie)),
+            (["gameover", "win", player], _) =>
+                    Some(Message::GameOver(GameState::Win(parse_player(player.parse().ok()?)?))),
+            (["gameover", "time", player], _) => Some(Message::GameOver(
+                    GameState::OutOfTime(parse_player(player.parse().ok()?)?))),
+            (["gameover", "resign", player], _) => Some(Message::GameOver(
+                    GameState::Resigned(parse_player(player.parse().ok()?)?))),
+            (["gameover", "draw"], _) => Some(Message::GameOver(GameState::DrawAgreed)),
+            (["gameover", "abort"], _) => Some(Message::GameOver(GameState::Aborted)),
+            (["move", _], Some(&[col])) if col > 0 => Some(Message::Move(col - 1)),
+            (["resign"], _) => Some(Message::Resign),
+            (["draw"], _) => Some(Message::OfferDraw),
+            (["accept"], _) => Some(Message::AcceptDraw),
+            (["quit"], _) => Some(Message::Quit),
+            _ => None,
+        };
+    }
+
+
+    // Write the message as a line of the protocol, without the newline.
+    pub fn format(&self) -> String {
+        return match self {
+            Message::Hello { config, player } => format!("hello {} {} {} {}",
+                    config.width(), config.height(), config.win_length(), player_number(*player)),
+            Message::Spectate { config } => format!("spectate {} {} {}",
+                    config.width(), config.height(), config.win_length()),
+            Message::Board(moves) => {
+                let words: Vec<String> = std::iter::once(String::from("board"))
+                        .chain(moves.iter().map(|col| (col + 1).to_string()))
+                        .collect();
+                words.join(" ")
+            }
+            Message::Clock(times) =>
+                    format!("clock {} {}", times[0].as_millis(), times[1].as_millis()),
+            Message::DrawOffer => String::from("drawoffer"),
+            Message::YourTurn => String::from("yourturn"),
+            Message::Error(text) => format!("error {text}"),
+            Message::GameOver(GameState::Win(player)) =>
+                    format!("gameover win {}", player_number(*player)),
+            Message::GameOver(GameState::OutOfTime(player)) =>
+                    format!("gameover time {}", player_number(*player)),
+            Message::GameOver(GameState::Resigned(player)) =>
+                    format!("gameover resign {}", player_number(*player)),
+            Message::GameOver(GameState::DrawAgreed) => String::from("gameover draw"),
+            Message::GameOver(GameState::Aborted) => String::from("gameover abort"),
+            Message::GameOver(_) => String::from("gameover tie"),
+            Message::Move(col) => format!("move {}", col + 1),
+            Message::Resign => String::from("resign"),
+            Message::OfferDraw => String::from("draw"),
+            Message::AcceptDraw => String::from("accept"),
+            Message::Quit => String::from("quit"),
+        };
+    }
+}
+
+
+// Send a message as one line.
+pub fn send(writer: &mut impl Write, message: &Message) -> io::Result<()> {
+    writeln!(writer, "{}", message.format())?;
+    return writer.flush();
+}
+
+
+// Read the next message. A closed connection or a line that isn't a message is an error.
+pub fn receive(reader: &mut impl BufRead) -> io::Result<Message> {
+    let mut line: String = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the connection was closed"));
+    }
+    return Message::parse(&line).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("unexpected message: {}", line.trim()))
+    });
+}
+
+
+impl RemotePlayer {
+    // Greet the player who just connected, telling them the board and which player they are.
+    pub fn new(stream: TcpStream, config: GameConfig, player: Player) -> io::Result<Self> {
+        let writer: TcpStream = stream.try_clone()?;
+        return RemotePlayer::with_reader(BufReader::new(stream), writer, config, player);
+    }
+
+
+    // Greet a player whose connection has already been read from, keeping whatever was read
+    // ahead in the reader.
+    pub fn with_reader(reader: BufReader<TcpStream>, mut writer: TcpStream, config: GameConfig,
+                       player: Player) -> io::Result<Self> {
+        send(&mut writer, &Message::Hello { config, player })?;
+        return Ok(RemotePlayer { reader, writer });
+    }
+
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        return send(&mut self.writer, message);
+    }
+
+
+    // Ask the player for their move until they send one that can be played.
+    fn receive_move(&mut self, view: &GameView) -> Move {
+        loop {
+            if send(&mut self.writer, &Message::YourTurn).is_err() {
+                return Move::Resign;
+            }
+            let col: usize = match receive(&mut self.reader) {
+                Ok(Message::Move(col)) => col,
+                Ok(Message::Resign) => return Move::Resign,
+                Ok(Message::OfferDraw) => return Move::OfferDraw,
+                Ok(Message::AcceptDraw) => return Move::AcceptDraw,
+                _ => return Move::Resign,
+            };
+            let error: MoveError = if col >= view.config().width() {
+                MoveError::ColumnOutOfRange
+            } else if !view.position().can_play(col) {
+                MoveError::ColumnFull
+            } else {
+                return Move::Play(col);
+            };
+            let text: String = render::describe_move_error(error, col);
+            if send(&mut self.writer, &Message::Error(text)).is_err() {
+                return Move::Resign;
+            }
+        }
+    }
+}
+
+
+// A player who leaves, loses their connection or sends something other than a move forfeits the
+// game, as if they resigned. On a clock, the player is only waited for until their time runs
+// out.
+impl MoveSource for RemotePlayer {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        let stream: &TcpStream = self.reader.get_ref();
+        let timeout: Option<Duration> = match stream.read_timeout() {
+            Ok(timeout) => timeout,
+            Err(_) => return Move::Resign,
+        };
+        if let Some(deadline) = view.deadline() {
+            // A zero timeout isn't allowed, and the clock has run out by the time it passes.
+            let time_left: Duration =
+                    deadline.saturating_duration_since(Instant::now()).max(MIN_READ_TIMEOUT);
+            if stream.set_read_timeout(Some(timeout.map_or(time_left, |t| t.min(time_left))))
+                    .is_err() {
+                return Move::Resign;
+            }
+        }
+        let chosen: Move = self.receive_move(view);
+        let _ = self.reader.get_ref().set_read_timeout(timeout);
+        return chosen;
+    }
+
+
+    fn is_interactive(&self) -> bool {
+        return true;
+    }
+}
+
+
+impl<R: Renderer> RemoteRenderer<R> {
+    pub fn new(local: R, stream: TcpStream, remote: Player, config: GameConfig) -> Self {
+        let spectators: Spectators = Spectators::new(config);
+        return RemoteRenderer { local, writer: stream, remote, spectators };
+    }
+
+
+    // Return the spectators of the game, to add more to.
+    pub fn spectators(&self) -> Spectators {
+        return self.spectators.clone();
+    }
+
+
+    pub fn into_local(self) -> R {
+        return self.local;
+    }
+}
+
+
+// Messages that can't be sent are dropped: a lost connection shows up as the joining player
+// forfeiting on their next turn.
+impl<R: Renderer> Renderer for RemoteRenderer<R> {
+    fn board_updated(&mut self, game: &ConnectFourGame) {
+        self.local.board_updated(game);
+        let _ = send(&mut self.writer, &Message::Board(game.history().to_vec()));
+        self.spectators.board_updated(game.history());
+    }
+
+
+    fn clock_updated(&mut self, times: [Duration; 2]) {
+        self.local.clock_updated(times);
+        let _ = send(&mut self.writer, &Message::Clock(times));
+        self.spectators.clock_updated(times);
+    }
+
+
+    fn prompt(&mut self, game: &ConnectFourGame) {
+        if game.active_player() == self.remote {
+            if game.draw_offer() == Some(self.remote.other()) {
+                let _ = send(&mut self.writer, &Message::DrawOffer);
+            }
+            self.local.message("Waiting for the other player...");
+        } else {
+            self.local.prompt(game);
+        }
+    }
+
+
+    fn invalid_move(&mut self, col: usize, error: MoveError) {
+        self.local.invalid_move(col, error);
+    }
+
+
+    fn game_over(&mut self, game: &ConnectFourGame, state: GameState) {
+        self.local.game_over(game, state);
+        // The final move hasn't been sent yet, since the board isn't updated after it.
+        let _ = send(&mut self.writer, &Message::Board(game.history().to_vec()));
+        let _ = send(&mut self.writer, &Message::GameOver(state));
+        self.spectators.board_updated(game.history());
+        self.spectators.game_over(state);
+    }
+
+
+    fn message(&mut self, text: &str) {
+        self.local.message(text);
+    }
+}
+
+
+impl Spectators {
+    pub fn new(config: GameConfig) -> Self {
+        let audience: Audience = Audience {
+            config,
+            moves: Vec::new(),
+            times: None,
+            result: None,
+            streams: Vec::new(),
+        };
+        return Spectators { audience: Arc::new(Mutex::new(audience)) };
+    }
+
+
+    // Start sending the game to the spectator on the given connection, beginning with the
+    // board and clocks so far and the result if the game is over.
+    pub fn add(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut audience = self.audience.lock().unwrap();
+        send(&mut stream, &Message::Spectate { config: audience.config })?;
+        send(&mut stream, &Message::Board(audience.moves.clone()))?;
+        if let Some(times) = audience.times {
+            send(&mut stream, &Message::Clock(times))?;
+        }
+        if let Some(result) = audience.result {
+            send(&mut stream, &Message::GameOver(result))?;
+        }
+        audience.streams.push(stream);
+        return Ok(());
+    }
+
+
+    pub fn len(&self) -> usize {
+        return self.audience.lock().unwrap().streams.len();
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+
+    // Send the moves played so far to every spectator.
+    pub fn board_updated(&self, moves: &[usize]) {
+        let mut audience = self.audience.lock().unwrap();
+        audience.moves = moves.to_vec();
+        audience.broadcast(&Message::Board(moves.to_vec()));
+    }
+
+
+    // Send the time each player has left to every spectator.
+    pub fn clock_updated(&self, times: [Duration; 2]) {
+        let mut audience = self.audience.lock().unwrap();
+        audience.times = Some(times);
+        audience.broadcast(&Message::Clock(times));
+    }
+
+
+    // Send how the game ended to every spectator.
+    pub fn game_over(&self, state: GameState) {
+        let mut audience = self.audience.lock().unwrap();
+        audience.result = Some(state);
+        audience.broadcast(&Message::GameOver(state));
+    }
+}
+
+
+impl Audience {
+    // Send the message to every spectator, and stop sending to those who have left.
+    fn broadcast(&mut self, message: &Message) {
+        self.streams.retain_mut(|stream| send(stream, message).is_ok());
+    }
+}
+
+
+// Play a game hosted on another machine, choosing moves with the given source and showing the
+// host's game with the renderer, until the game ends or the source quits. If a player has
+// already joined, watch the game instead.
+pub fn join(stream: TcpStream, source: &mut dyn MoveSource, renderer: &mut dyn Renderer)
+        -> io::Result<()> {
+    let mut reader: BufReader<TcpStream> = BufReader::new(stream.try_clone()?);
+    let mut writer: TcpStream = stream;
+    return match receive(&mut reader)? {
+        Message::Hello { config, player } =>
+                play_hosted(&mut reader, &mut writer, config, player, source, renderer),
+        Message::Spectate { config } => watch_hosted(&mut reader, config, renderer),
+        other => Err(io::Error::new(io::ErrorKind::InvalidData,
+                                    format!("expected a greeting: {}", other.format()))),
+    };
+}
+
+
+// Play the rest of a hosted game after the host's greeting, which gave the board config and
+// which player this side plays as.
+pub fn play_hosted(reader: &mut impl BufRead, writer: &mut impl Write, config: GameConfig,
+                   player: Player, source: &mut dyn MoveSource, renderer: &mut dyn Renderer)
+        -> io::Result<()> {
+    renderer.message(&format!("You are player {}.", player_number(player)));
+    let mut game: ConnectFourGame = ConnectFourGame::new(config);
+    loop {
+        match receive(reader)? {
+            Message::Board(moves) => {
+                game = replay(config, &moves)?;
+                renderer.board_updated(&game);
+            }
+            Message::Clock(times) => renderer.clock_updated(times),
+            Message::DrawOffer => game.offer_draw(player.other()),
+            Message::YourTurn => {
+                renderer.prompt(&game);
+                loop {
+                    let reply: Message = match source.choose_move(&GameView::new(&game)) {
+                        Move::Play(col) => Message::Move(col),
+                        Move::Resign => Message::Resign,
+                        Move::OfferDraw => Message::OfferDraw,
+                        Move::AcceptDraw if game.draw_offer() == Some(player.other()) =>
+                                Message::AcceptDraw,
+                        Move::AcceptDraw => {
+                            renderer.message("There's no draw offer to accept.");
+                            continue;
+                        }
+                        Move::Quit => return send(writer, &Message::Quit),
+                        _ => {
+                            renderer.message("That isn't available in a network game.");
+                            continue;
+                        }
+                    };
+                    send(writer, &reply)?;
+                    break;
+                }
+            }
+            Message::Error(text) => renderer.message(&text),
+            Message::GameOver(state) => {
+                renderer.game_over(&game, state);
+                return Ok(());
+            }
+            other => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                               format!("unexpected message: {}", other.format()))),
+        }
+    }
+}
+
+
+// Watch a hosted game after the host's greeting, which gave the board config, until it ends.
+pub fn watch_hosted(reader: &mut impl BufRead, config: GameConfig, renderer: &mut dyn Renderer)
+        -> io::Result<()> {
+    renderer.message("You are watching the game.");
+    let mut game: ConnectFourGame = ConnectFourGame::new(config);
+    loop {
+        match receive(reader)? {
+            Message::Board(moves) => {
+                game = replay(config, &moves)?;
+                renderer.board_updated(&game);
+            }
+            Message::Clock(times) => renderer.clock_updated(times),
+            Message::GameOver(state) => {
+                renderer.game_over(&game, state);
+                return Ok(());
+            }
+            other => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                               format!("unexpected message: {}", other.format()))),
+        }
+    }
+}
+
+
+// Play the host's moves on a new game.
+fn replay(config: GameConfig, moves: &[usize]) -> io::Result<ConnectFourGame> {
+    let mut game: ConnectFourGame = ConnectFourGame::new(config);
+    for col in moves {
+        game.play_move(game.active_player(), *col).map_err(|error| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("illegal move: {error:?}"))
+        })?;
+    }
+    return Ok(game);
+}
+
+
+fn player_number(player: Player) -> usize {
+    return match player {
+        Player::One => 1,
+        Player::Two => 2,
+    };
+}
+
+
+fn parse_player(number: usize) -> Option<Player> {
+    return match number {
+        1 => Some(Player::One),
+        2 => Some(Player::Two),
+        _ => None,
+    };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_source::ScriptedSource;
+    use crate::notation;
+    use crate::render::TextRenderer;
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Return both ends of a connection over localhost.
+    fn connect() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (host, _) = listener.accept().unwrap();
+        return (host, client);
+    }
+
+    #[test]
+    fn test_messages_round_trip() {
+        let messages = [
+            Message::Hello { config: GameConfig::new(9, 7, 5).unwrap(), player: Player::Two },
+            Message::Spectate { config: GameConfig::default() },
+            Message::Board(Vec::new()),
+            Message::Board(vec![3, 3, 4]),
+            Message::Clock([Duration::from_millis(299_950), Duration::from_secs(4)]),
+            Message::YourTurn,
+            Message::Error(String::from("Column 4 is full. Try another column.")),
+            Message::GameOver(GameState::Win(Player::One)),
+            Message::GameOver(GameState::Tie),
+            Message::GameOver(GameState::OutOfTime(Player::Two)),
+            Message::GameOver(GameState::Resigned(Player::One)),
+            Message::GameOver(GameState::DrawAgreed),
+            Message::GameOver(GameState::Aborted),
+            Message::DrawOffer,
+            Message::Move(6),
+            Message::Resign,
+            Message::OfferDraw,
+            Message::AcceptDraw,
+            Message::Quit,
+        ];
+        for message in messages {
+            assert_eq!(Message::parse(&message.format()), Some(message));
+        }
+        assert_eq!(Message::parse("move 0"), None);
+        assert_eq!(Message::parse("hello 7 6 4 3"), None);
+        assert_eq!(Message::parse("dance"), None);
+    }
+
+    #[test]
+    fn test_remote_player_rejects_full_column() {
+        let (host, client) = connect();
+        let remote = thread::spawn(move || {
+            let mut reader = BufReader::new(client.try_clone().unwrap());
+            let mut writer = client;
+            let mut received = Vec::new();
+            for reply in [Message::Move(0), Message::Move(1)] {
+                received.push(receive(&mut reader).unwrap());
+                received.push(receive(&mut reader).unwrap());
+                send(&mut writer, &reply).unwrap();
+            }
+            return received;
+        });
+        let game = notation::parse(GameConfig::default(), "111111").unwrap();
+        let mut player = RemotePlayer::new(host, game.config(), Player::One).unwrap();
+        // Column 1 is full, so the player is told and asked again.
+        assert_eq!(player.choose_move(&GameView::new(&game)), Move::Play(1));
+        drop(player);
+        let received = remote.join().unwrap();
+        assert_eq!(received[0], Message::Hello { config: game.config(), player: Player::One });
+        assert_eq!(received[1], Message::YourTurn);
+        assert!(matches!(received[2], Message::Error(_)));
+        assert_eq!(received[3], Message::YourTurn);
+    }
+
+    #[test]
+    fn test_remote_player_forfeits_by_leaving() {
+        let game = ConnectFourGame::default();
+        let (host, client) = connect();
+        let mut player = RemotePlayer::new(host, game.config(), Player::One).unwrap();
+        send(&mut client.try_clone().unwrap(), &Message::Quit).unwrap();
+        assert_eq!(player.choose_move(&GameView::new(&game)), Move::Resign);
+        drop(client);
+        assert_eq!(player.choose_move(&GameView::new(&game)), Move::Resign);
+    }
+
+    #[test]
+    fn test_remote_player_not_waited_for_past_deadline() {
+        let (host, client) = connect();
+        let game = ConnectFourGame::default();
+        let mut player = RemotePlayer::new(host, game.config(), Player::One).unwrap();
+        let view = GameView::with_clock(&game, Duration::ZERO, Duration::from_millis(50));
+        let started = Instant::now();
+        player.choose_move(&view);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(player.reader.get_ref().read_timeout().unwrap(), None);
+        drop(client);
+    }
+
+    #[test]
+    fn test_late_spectator_catches_up() {
+        let spectators = Spectators::new(GameConfig::default());
+        let greeting = Message::Spectate { config: GameConfig::default() };
+        spectators.board_updated(&[3, 3]);
+        let (early_host, early) = connect();
+        spectators.add(early_host).unwrap();
+        spectators.board_updated(&[3, 3, 4]);
+        let (late_host, late) = connect();
+        spectators.add(late_host).unwrap();
+        let (gone_host, gone) = connect();
+        spectators.add(gone_host).unwrap();
+        drop(gone);
+        spectators.board_updated(&[3, 3, 4, 4]);
+        spectators.game_over(GameState::Tie);
+        // The spectator who left is dropped once sending to them fails.
+        assert_eq!(spectators.len(), 2);
+        let mut early = BufReader::new(early);
+        let mut late = BufReader::new(late);
+        assert_eq!(receive(&mut early).unwrap(), greeting);
+        assert_eq!(receive(&mut early).unwrap(), Message::Board(vec![3, 3]));
+        assert_eq!(receive(&mut early).unwrap(), Message::Board(vec![3, 3, 4]));
+        assert_eq!(receive(&mut late).unwrap(), greeting);
+        assert_eq!(receive(&mut late).unwrap(), Message::Board(vec![3, 3, 4]));
+        for spectator in [&mut early, &mut late] {
+            assert_eq!(receive(spectator).unwrap(), Message::Board(vec![3, 3, 4, 4]));
+            assert_eq!(receive(spectator).unwrap(), Message::GameOver(GameState::Tie));
+        }
+    }
+
+    #[test]
+    fn test_join_as_spectator() {
+        let (host, client) = connect();
+        let watcher = thread::spawn(move || {
+            let mut renderer = TextRenderer::new(Vec::new());
+            join(client, &mut ScriptedSource::new(&[]), &mut renderer).unwrap();
+            return String::from_utf8(renderer.into_inner()).unwrap();
+        });
+        let game = notation::parse(GameConfig::default(), "1212121").unwrap();
+        let spectators = Spectators::new(game.config());
+        spectators.add(host).unwrap();
+        spectators.board_updated(game.history());
+        spectators.game_over(game.state());
+        let output = watcher.join().unwrap();
+        assert!(output.starts_with("You are watching the game.\n"), "{output}");
+        assert!(output.ends_with("Player 1 wins.\nMoves: 1212121\n"), "{output}");
+    }
+
+    #[test]
+    fn test_join() {
+        let (host, client) = connect();
+        let joiner = thread::spawn(move || {
+            let mut source = ScriptedSource::new(&[1, 1, 1]);
+            let mut renderer = TextRenderer::new(Vec::new());
+            join(client, &mut source, &mut renderer).unwrap();
+            return String::from_utf8(renderer.into_inner()).unwrap();
+        });
+        // Play the host's side by hand: player 1 wins in column 1 while player 2 plays column 2.
+        let mut reader = BufReader::new(host.try_clone().unwrap());
+        let mut writer = host;
+        let mut game = ConnectFourGame::default();
+        send(&mut writer, &Message::Hello { config: game.config(), player: Player::Two }).unwrap();
+        for _ in 0..3 {
+            game.play_move(Player::One, 0).unwrap();
+            send(&mut writer, &Message::Board(game.history().to_vec())).unwrap();
+            send(&mut writer, &Message::YourTurn).unwrap();
+            assert_eq!(receive(&mut reader).unwrap(), Message::Move(1));
+            game.play_move(Player::Two, 1).unwrap();
+        }
+        game.play_move(Player::One, 0).unwrap();
+        send(&mut writer, &Message::Board(game.history().to_vec())).unwrap();
+        send(&mut writer, &Message::GameOver(game.state())).unwrap();
+        let output = joiner.join().unwrap();
+        assert!(output.starts_with("You are player 2.\n"), "{output}");
+        assert!(output.ends_with("Player 1 wins.\nMoves: 1212121\n"), "{output}");
+    }
+}
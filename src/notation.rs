@@ -0,0 +1,149 @@
+use crate::game::ConnectFourGame;
+use crate::game::GameConfig;
+use crate::game::MoveError;
+
+
+// The widest board the notation can describe, since each move is a single digit.
+const MAX_NOTATION_WIDTH: usize = 9;
+
+
+// The reasons a move sequence can fail to parse.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum NotationError {
+    // The board has more columns than there are digits.
+    UnsupportedWidth,
+    // The character at the given move number (1-indexed) isn't a column digit.
+    InvalidCharacter { move_number: usize, character: char },
+    // The move at the given move number (1-indexed) into the given column (1-indexed) was
+    // rejected by the game.
+    IllegalMove { move_number: usize, column: usize, error: MoveError },
+}
+
+
+// Play out a game from the standard move sequence notation, in which each move is written as
+// its 1-indexed column number, e.g. "4453". Every move is checked against the rules in turn,
+// and the error reports the first one that fails.
+pub fn parse(config: GameConfig, moves: &str) -> Result<ConnectFourGame, NotationError> {
+    if config.width() > MAX_NOTATION_WIDTH {
+        return Err(NotationError::UnsupportedWidth);
+    }
+    let mut game: ConnectFourGame = ConnectFourGame::new(config);
+    for (i, character) in moves.chars().enumerate() {
+        let move_number: usize = i + 1;
+        let column: usize = match character.to_digit(10) {
+            Some(digit) if digit > 0 => digit as usize,
+            _ => return Err(NotationError::InvalidCharacter { move_number, character }),
+        };
+        if let Err(error) = game.play_move(game.active_player(), column - 1) {
+            return Err(NotationError::IllegalMove { move_number, column, error });
+        }
+    }
+    return Ok(game);
+}
+
+
+// Write the moves played in the given game in the move sequence notation.
+pub fn serialize(game: &ConnectFourGame) -> Result<String, NotationError> {
+    if game.config().width() > MAX_NOTATION_WIDTH {
+        return Err(NotationError::UnsupportedWidth);
+    }
+    return Ok(game.history().iter().map(|col| (col + 1).to_string()).collect());
+}
+
+
+// Positions for the tests of the modules that analyse them.
+#[cfg(test)]
+pub mod test_positions {
+    use crate::game::GameConfig;
+    use crate::game::Player;
+    use crate::position::Position;
+
+    // Return the position on the standard board reached by the given moves.
+    pub fn position(moves: &str) -> Position {
+        return super::parse(GameConfig::default(), moves).unwrap().position().clone();
+    }
+
+    // Return a full standard board that neither player has won.
+    pub fn full_board() -> Position {
+        let column: Vec<Player> = [Player::One, Player::Two].repeat(3);
+        return Position::from_columns(GameConfig::default(), &vec![column; 7]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::game::Player;
+
+    #[test]
+    fn test_parse_empty() {
+        let game = parse(GameConfig::default(), "").unwrap();
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn test_parse_moves() {
+        let game = parse(GameConfig::default(), "4453").unwrap();
+        assert_eq!(game.history(), &[3, 3, 4, 2]);
+        assert_eq!(game.board()[3], vec![Player::One, Player::Two]);
+        assert_eq!(game.active_player(), Player::One);
+    }
+
+    #[test]
+    fn test_parse_win() {
+        let game = parse(GameConfig::default(), "1212121").unwrap();
+        assert_eq!(game.state(), GameState::Win(Player::One));
+    }
+
+    #[test]
+    fn test_parse_invalid_character() {
+        assert_eq!(parse(GameConfig::default(), "44x3").err(),
+                   Some(NotationError::InvalidCharacter { move_number: 3, character: 'x' }));
+        assert_eq!(parse(GameConfig::default(), "0").err(),
+                   Some(NotationError::InvalidCharacter { move_number: 1, character: '0' }));
+    }
+
+    #[test]
+    fn test_parse_column_out_of_range() {
+        assert_eq!(parse(GameConfig::default(), "448").err(),
+                   Some(NotationError::IllegalMove {
+                       move_number: 3, column: 8, error: MoveError::ColumnOutOfRange }));
+    }
+
+    #[test]
+    fn test_parse_column_full() {
+        assert_eq!(parse(GameConfig::default(), "1111111").err(),
+                   Some(NotationError::IllegalMove {
+                       move_number: 7, column: 1, error: MoveError::ColumnFull }));
+    }
+
+    #[test]
+    fn test_parse_move_after_win() {
+        assert_eq!(parse(GameConfig::default(), "12121214").err(),
+                   Some(NotationError::IllegalMove {
+                       move_number: 8, column: 4, error: MoveError::GameAlreadyOver }));
+    }
+
+    #[test]
+    fn test_parse_wide_board_unsupported() {
+        let config = GameConfig::new(10, 8, 4).unwrap();
+        assert_eq!(parse(config, "1").err(), Some(NotationError::UnsupportedWidth));
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let game = parse(GameConfig::default(), "4453621").unwrap();
+        assert_eq!(serialize(&game), Ok(String::from("4453621")));
+    }
+
+    #[test]
+    fn test_serialize_after_undo() {
+        let mut game = parse(GameConfig::default(), "445").unwrap();
+        game.undo();
+        assert_eq!(serialize(&game), Ok(String::from("44")));
+    }
+}
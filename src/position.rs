@@ -0,0 +1,561 @@
+use crate::game::Cell;
+use crate::game::GameConfig;
+use crate::game::Player;
+use std::vec::Vec;
+
+
+// A set of board cells, one bit per cell. Cell (col, row) is bit `col * (height + 1) + row`, so
+// each column takes `height + 1` bits: the extra bit at the top of every column is always
+// empty and stops lines from wrapping from one column into the next. A u128 rather than a u64
+// is used so the larger custom boards (e.g. 10x8, which needs 90 bits) still fit.
+pub type Bitboard = u128;
+
+
+// A random key for every player and bit of a bitboard. The Zobrist hash of a position is the
+// XOR of the keys of its tokens, which can be updated with one XOR per move played or undone.
+const ZOBRIST_KEYS: [[u64; Bitboard::BITS as usize]; 2] = zobrist_keys();
+
+
+// The four directions a line can run in, as the bit shift between neighbouring cells.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Direction {
+    Vertical,
+    Horizontal,
+    ForwardDiagonal,  // Shaped like '/'.
+    BackDiagonal,  // Shaped like '\'.
+}
+
+
+pub const DIRECTIONS: [Direction; 4] = [
+    Direction::Vertical,
+    Direction::Horizontal,
+    Direction::ForwardDiagonal,
+    Direction::BackDiagonal,
+];
+
+
+// A board position stored as one bitboard per player plus the height of every column, which
+// makes playing a move, checking its legality and detecting a line all constant-time.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Position {
+    config: GameConfig,
+    masks: [Bitboard; 2],  // The tokens of player 1 and player 2.
+    heights: Vec<usize>,
+    moves: usize,
+    hash: u64,
+}
+
+
+impl Position {
+    pub fn new(config: GameConfig) -> Self {
+        return Position {
+            config,
+            masks: [0, 0],
+            heights: vec![0; config.width()],
+            moves: 0,
+            hash: 0,
+        };
+    }
+
+
+    // Build a position from a vector of columns (column 0 on the left, rows bottom-to-top)
+    // holding the player in each occupied cell. The arrangement doesn't need to be reachable
+    // in a real game, which makes this handy for setting up test positions.
+    pub fn from_columns(config: GameConfig, columns: &[Vec<Player>]) -> Self {
+        let mut position: Position = Position::new(config);
+        for (col, column) in columns.iter().enumerate() {
+            for player in column {
+                position.place(*player, col);
+            }
+        }
+        return position;
+    }
+
+
+    pub fn config(&self) -> GameConfig {
+        return self.config;
+    }
+
+
+    // Return the number of tokens on the board.
+    pub fn moves(&self) -> usize {
+        return self.moves;
+    }
+
+
+    // Return the Zobrist hash of the position. Positions with the same tokens in the same cells
+    // have the same hash, however the moves were ordered, so it can key transposition tables.
+    pub fn hash(&self) -> u64 {
+        return self.hash;
+    }
+
+
+    // Return the player to move, assuming the players have alternated since player 1 started.
+    pub fn to_move(&self) -> Player {
+        return if self.moves.is_multiple_of(2) { Player::One } else { Player::Two };
+    }
+
+
+    // Return the number of tokens in the given column.
+    pub fn column_height(&self, col: usize) -> usize {
+        return self.heights[col];
+    }
+
+
+    // Return whether a token can be dropped into the given column.
+    pub fn can_play(&self, col: usize) -> bool {
+        return col < self.config.width() && self.heights[col] < self.config.height();
+    }
+
+
+    // Return whether every column is full.
+    pub fn is_full(&self) -> bool {
+        return self.moves == self.config.width() * self.config.height();
+    }
+
+
+    // Return the contents of the given cell.
+    pub fn cell(&self, col: usize, row: usize) -> Cell {
+        let bit: Bitboard = self.cell_bit(col, row);
+        if self.masks[0] & bit != 0 {
+            return Cell::Occupied(Player::One);
+        }
+        if self.masks[1] & bit != 0 {
+            return Cell::Occupied(Player::Two);
+        }
+        return Cell::Empty;
+    }
+
+
+    // Return the board as a vector of columns, in the layout accepted by `from_columns`.
+    pub fn columns(&self) -> Vec<Vec<Player>> {
+        return (0..self.config.width())
+                .map(|col| (0..self.heights[col]).map(|row| self.player_at(col, row)).collect())
+                .collect();
+    }
+
+
+    // Drop the token of the player to move into the given column, which must be playable.
+    pub fn play(&mut self, col: usize) {
+        self.place(self.to_move(), col);
+    }
+
+
+    // Drop the given player's token into the given column, which must be playable.
+    pub fn place(&mut self, player: Player, col: usize) {
+        let bit: Bitboard = self.cell_bit(col, self.heights[col]);
+        self.masks[player_index(player)] |= bit;
+        self.heights[col] += 1;
+        self.moves += 1;
+        self.hash ^= zobrist_key(player, bit);
+    }
+
+
+    // Remove the topmost token from the given column, which must not be empty.
+    pub fn undo(&mut self, col: usize) {
+        self.heights[col] -= 1;
+        let player: Player = self.player_at(col, self.heights[col]);
+        let bit: Bitboard = self.cell_bit(col, self.heights[col]);
+        self.masks[player_index(player)] &= !bit;
+        self.moves -= 1;
+        self.hash ^= zobrist_key(player, bit);
+    }
+
+
+    // Return whether playing the given column would complete a line for the player to move.
+    pub fn is_winning_move(&self, col: usize) -> bool {
+        return self.is_winning_move_for(self.to_move(), col);
+    }
+
+
+    // Return whether dropping the given player's token into the given column would complete a
+    // line for them. For the player not to move, this is a threat they are making.
+    pub fn is_winning_move_for(&self, player: Player, col: usize) -> bool {
+        if !self.can_play(col) {
+            return false;
+        }
+        let mask: Bitboard = self.masks[player_index(player)]
+                | self.cell_bit(col, self.heights[col]);
+        return DIRECTIONS.iter().any(|direction| self.line_starts(mask, *direction) != 0);
+    }
+
+
+    // Return whether the given player has a line of the winning length anywhere on the board.
+    pub fn has_won(&self, player: Player) -> bool {
+        return DIRECTIONS.iter().any(|direction| self.line_cells(player, *direction) != 0);
+    }
+
+
+    // Return every cell that is part of a line of the winning length for the given player in
+    // the given direction.
+    pub fn line_cells(&self, player: Player, direction: Direction) -> Bitboard {
+        let shift: usize = self.shift(direction);
+        let mut cells: Bitboard = self.line_starts(self.masks[player_index(player)], direction);
+        for _ in 1..self.config.win_length() {
+            cells |= cells << shift;
+        }
+        return cells;
+    }
+
+
+    // Return every cell that is part of a line of the winning length for the given player in
+    // the given direction passing through the given cell.
+    pub fn lines_through(&self, player: Player, cell: Bitboard, direction: Direction)
+            -> Bitboard {
+        return self.lines_through_mask(self.masks[player_index(player)], cell, direction);
+    }
+
+
+    // Return the empty cells that would complete a line of the winning length for the given
+    // player, whether or not a token can be dropped into them yet.
+    pub fn threat_cells(&self, player: Player) -> Bitboard {
+        let mask: Bitboard = self.masks[player_index(player)];
+        let mut threats: Bitboard = 0;
+        for col in 0..self.config.width() {
+            for row in self.heights[col]..self.config.height() {
+                let cell: Bitboard = self.cell_bit(col, row);
+                let completes = |direction: &Direction| {
+                    return self.lines_through_mask(mask | cell, cell, *direction) != 0;
+                };
+                if DIRECTIONS.iter().any(completes) {
+                    threats |= cell;
+                }
+            }
+        }
+        return threats;
+    }
+
+
+    // Return the columns the given player would win in by dropping a token there now, in
+    // ascending order.
+    pub fn winning_columns(&self, player: Player) -> Vec<usize> {
+        let threats: Bitboard = self.threat_cells(player);
+        return (0..self.config.width())
+                .filter(|col| self.can_play(*col)
+                        && threats & self.cell_bit(*col, self.heights[*col]) != 0)
+                .collect();
+    }
+
+
+    // Return the (col, row) coordinates of the given cells, ordered by column then row.
+    pub fn coordinates(&self, cells: Bitboard) -> Vec<(usize, usize)> {
+        let mut coordinates: Vec<(usize, usize)> = Vec::new();
+        for col in 0..self.config.width() {
+            for row in 0..self.config.height() {
+                if cells & self.cell_bit(col, row) != 0 {
+                    coordinates.push((col, row));
+                }
+            }
+        }
+        return coordinates;
+    }
+
+
+    // Return the cells holding the given player's tokens.
+    pub fn mask(&self, player: Player) -> Bitboard {
+        return self.masks[player_index(player)];
+    }
+
+
+    // Return every straight line of `win_length` cells that fits on the board, in any
+    // direction. A player who fills one of these lines wins.
+    pub fn lines(&self) -> Vec<Bitboard> {
+        let width: i64 = self.config.width() as i64;
+        let height: i64 = self.config.height() as i64;
+        let length: i64 = self.config.win_length() as i64;
+        let steps: [(i64, i64); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+        let mut lines: Vec<Bitboard> = Vec::new();
+        for (dc, dr) in steps {
+            for col in 0..width {
+                for row in 0..height {
+                    let end_col: i64 = col + dc * (length - 1);
+                    let end_row: i64 = row + dr * (length - 1);
+                    if end_col >= width || end_row < 0 || end_row >= height {
+                        continue;
+                    }
+                    let mut line: Bitboard = 0;
+                    for i in 0..length {
+                        line |= self.cell_bit((col + dc * i) as usize, (row + dr * i) as usize);
+                    }
+                    lines.push(line);
+                }
+            }
+        }
+        return lines;
+    }
+
+
+    // Return the bit for the given cell.
+    pub fn cell_bit(&self, col: usize, row: usize) -> Bitboard {
+        return 1 << (col * (self.config.height() + 1) + row);
+    }
+
+
+    // Return the bit for the topmost token in the given column, which must not be empty.
+    pub fn top_cell_bit(&self, col: usize) -> Bitboard {
+        return self.cell_bit(col, self.heights[col] - 1);
+    }
+
+
+    // Return every cell that is part of a line of the winning length among the given cells in
+    // the given direction passing through the given cell.
+    fn lines_through_mask(&self, mask: Bitboard, cell: Bitboard, direction: Direction)
+            -> Bitboard {
+        let shift: usize = self.shift(direction);
+        let starts: Bitboard = self.line_starts(mask, direction);
+        let mut cells: Bitboard = 0;
+        let mut before: Bitboard = cell;  // Shifted one step at a time, which can't overflow.
+        for _ in 0..self.config.win_length() {
+            cells |= starts & before;
+            before >>= shift;
+        }
+        for _ in 1..self.config.win_length() {
+            cells |= cells << shift;
+        }
+        return cells;
+    }
+
+
+    // Return the bits at which a line of the winning length starts in the given direction.
+    // Each step keeps only the cells whose next neighbour is also set, so after
+    // `win_length - 1` steps only the starts of long-enough lines remain.
+    fn line_starts(&self, mask: Bitboard, direction: Direction) -> Bitboard {
+        let shift: usize = self.shift(direction);
+        let mut starts: Bitboard = mask;
+        for _ in 1..self.config.win_length() {
+            starts &= starts >> shift;
+        }
+        return starts;
+    }
+
+
+    // Return the player occupying the given cell, which must not be empty.
+    fn player_at(&self, col: usize, row: usize) -> Player {
+        let bit: Bitboard = self.cell_bit(col, row);
+        return if self.masks[0] & bit != 0 { Player::One } else { Player::Two };
+    }
+
+
+    // Return the distance in bits between neighbouring cells in the given direction.
+    fn shift(&self, direction: Direction) -> usize {
+        let column_bits: usize = self.config.height() + 1;
+        return match direction {
+            Direction::Vertical => 1,
+            Direction::Horizontal => column_bits,
+            Direction::ForwardDiagonal => column_bits + 1,
+            Direction::BackDiagonal => column_bits - 1,
+        };
+    }
+}
+
+
+// Return the Zobrist key for the given player's token in the given cell.
+pub fn zobrist_key(player: Player, cell: Bitboard) -> u64 {
+    return ZOBRIST_KEYS[player_index(player)][cell.trailing_zeros() as usize];
+}
+
+
+// Generate the Zobrist keys with splitmix64 from a fixed seed, so that hashes are the same on
+// every run.
+const fn zobrist_keys() -> [[u64; Bitboard::BITS as usize]; 2] {
+    let mut keys: [[u64; Bitboard::BITS as usize]; 2] = [[0; Bitboard::BITS as usize]; 2];
+    let mut state: u64 = 0;
+    let mut i: usize = 0;
+    while i < 2 * Bitboard::BITS as usize {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z: u64 = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i % 2][i / 2] = z ^ (z >> 31);
+        i += 1;
+    }
+    return keys;
+}
+
+
+fn player_index(player: Player) -> usize {
+    return match player {
+        Player::One => 0,
+        Player::Two => 1,
+    };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P1: Player = Player::One;
+    const P2: Player = Player::Two;
+
+    #[test]
+    fn test_new_position_is_empty() {
+        let position = Position::new(GameConfig::default());
+        assert_eq!(position.moves(), 0);
+        assert_eq!(position.to_move(), Player::One);
+        assert_eq!(position.columns(), vec![Vec::<Player>::new(); 7]);
+    }
+
+    #[test]
+    fn test_play_and_undo() {
+        let mut position = Position::new(GameConfig::default());
+        position.play(3);
+        position.play(3);
+        position.play(4);
+        assert_eq!(position.cell(3, 0), Cell::Occupied(Player::One));
+        assert_eq!(position.cell(3, 1), Cell::Occupied(Player::Two));
+        assert_eq!(position.cell(4, 0), Cell::Occupied(Player::One));
+        assert_eq!(position.to_move(), Player::Two);
+        position.undo(3);
+        assert_eq!(position.cell(3, 1), Cell::Empty);
+        assert_eq!(position.column_height(3), 1);
+        assert_eq!(position.moves(), 2);
+    }
+
+    #[test]
+    fn test_hash_ignores_move_order() {
+        let mut position = Position::new(GameConfig::default());
+        for col in [0, 1, 2, 3] {
+            position.play(col);
+        }
+        let mut transposed = Position::new(GameConfig::default());
+        for col in [2, 3, 0, 1] {
+            transposed.play(col);
+        }
+        assert_eq!(position.hash(), transposed.hash());
+        // The same cells with the players swapped are a different position.
+        let mut swapped = Position::new(GameConfig::default());
+        for col in [1, 0, 3, 2] {
+            swapped.play(col);
+        }
+        assert_ne!(position.hash(), swapped.hash());
+    }
+
+    #[test]
+    fn test_hash_restored_by_undo() {
+        let mut position = Position::new(GameConfig::default());
+        position.play(3);
+        let hash = position.hash();
+        position.play(4);
+        assert_ne!(position.hash(), hash);
+        position.undo(4);
+        assert_eq!(position.hash(), hash);
+        position.undo(3);
+        assert_eq!(position.hash(), 0);
+    }
+
+    #[test]
+    fn test_can_play() {
+        let mut position = Position::new(GameConfig::default());
+        for _ in 0..6 {
+            assert!(position.can_play(0));
+            position.play(0);
+        }
+        assert!(!position.can_play(0));
+        assert!(!position.can_play(7));
+    }
+
+    #[test]
+    fn test_columns_round_trip() {
+        let columns = vec![
+            vec![P1, P2], vec![], vec![P2], vec![P1, P1, P2], vec![], vec![], vec![P1],
+        ];
+        let position = Position::from_columns(GameConfig::default(), &columns);
+        assert_eq!(position.columns(), columns);
+        assert_eq!(position.moves(), 7);
+    }
+
+    #[test]
+    fn test_is_winning_move() {
+        let mut position = Position::new(GameConfig::default());
+        for col in [0, 1, 0, 1, 0, 1] {
+            position.play(col);
+        }
+        assert!(position.is_winning_move(0));
+        assert!(!position.is_winning_move(2));
+        assert!(position.is_winning_move_for(P2, 1));
+        assert!(!position.is_winning_move_for(P2, 0));
+    }
+
+    #[test]
+    fn test_lines_do_not_wrap_between_columns() {
+        // Player 1 holds the top of column 0 and the bottom of columns 1 to 3, which would be
+        // adjacent bits without the empty bit at the top of each column.
+        let columns = vec![vec![P2, P2, P2, P2, P2, P1], vec![P1], vec![P1], vec![P1]];
+        let position = Position::from_columns(GameConfig::new(4, 6, 4).unwrap(), &columns);
+        assert!(!position.has_won(P1));
+        assert!(position.has_won(P2));
+    }
+
+    #[test]
+    fn test_line_cells() {
+        let columns = vec![
+            vec![P1], vec![P2, P1], vec![P2, P2, P1], vec![P2, P2, P2, P1], vec![P1],
+        ];
+        let position = Position::from_columns(GameConfig::new(5, 5, 4).unwrap(), &columns);
+        let cells = position.line_cells(P1, Direction::ForwardDiagonal);
+        let expected = position.cell_bit(0, 0) | position.cell_bit(1, 1)
+                | position.cell_bit(2, 2) | position.cell_bit(3, 3);
+        assert_eq!(cells, expected);
+        assert_eq!(position.line_cells(P1, Direction::Horizontal), 0);
+    }
+
+    #[test]
+    fn test_lines_through() {
+        // Player 1 has a vertical line in column 0 and a horizontal line along row 0.
+        let columns = vec![
+            vec![P1, P1, P1, P1], vec![P1], vec![P1], vec![P1], vec![], vec![], vec![P2],
+        ];
+        let position = Position::from_columns(GameConfig::default(), &columns);
+        let top: Bitboard = position.cell_bit(0, 3);
+        assert_eq!(position.coordinates(position.lines_through(P1, top, Direction::Vertical)),
+                   vec![(0, 0), (0, 1), (0, 2), (0, 3)]);
+        assert_eq!(position.lines_through(P1, top, Direction::Horizontal), 0);
+        let corner: Bitboard = position.cell_bit(0, 0);
+        assert_eq!(position.coordinates(position.lines_through(P1, corner, Direction::Horizontal)),
+                   vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn test_threat_cells() {
+        // Player 1 has three on a diagonal whose last cell, (3, 3), can't be played yet. Their
+        // three along the bottom is blocked by player 2.
+        let columns = vec![
+            vec![P1], vec![P1, P1], vec![P1, P2, P1], vec![P2, P2], vec![], vec![], vec![],
+        ];
+        let position = Position::from_columns(GameConfig::default(), &columns);
+        assert_eq!(position.coordinates(position.threat_cells(P1)), vec![(3, 3)]);
+        assert!(position.winning_columns(P1).is_empty());
+        let columns = vec![vec![], vec![P1], vec![P1], vec![P1], vec![P2, P2], vec![], vec![P2]];
+        let position = Position::from_columns(GameConfig::default(), &columns);
+        assert_eq!(position.coordinates(position.threat_cells(P1)), vec![(0, 0)]);
+        assert_eq!(position.threat_cells(P2), 0);
+        assert_eq!(position.winning_columns(P1), vec![0]);
+    }
+
+    #[test]
+    fn test_lines_count() {
+        // The standard board has 24 horizontal, 21 vertical and 12 + 12 diagonal lines of 4.
+        let position = Position::new(GameConfig::default());
+        assert_eq!(position.lines().len(), 69);
+        assert!(position.lines().iter().all(|line| line.count_ones() == 4));
+    }
+
+    #[test]
+    fn test_largest_board() {
+        let config = GameConfig::new(10, 8, 4).unwrap();
+        let mut position = Position::new(config);
+        for col in [6, 6, 7, 7, 8, 8] {
+            position.play(col);
+        }
+        assert!(position.is_winning_move(9));
+        assert!(position.is_winning_move(5));
+        position.play(9);
+        assert!(position.has_won(P1));
+    }
+}
@@ -1,74 +1,201 @@
+use rust_connect_4::clock;
+use rust_connect_4::game::ConnectFourGame;
+use rust_connect_4::game::GameConfig;
+use rust_connect_4::game::GameState;
+use rust_connect_4::game::MoveError;
+use rust_connect_4::game::Player;
+use rust_connect_4::notation;
+use rust_connect_4::render;
+use rust_connect_4::render::Renderer;
+use std::time::Duration;
 use std::vec::Vec;
 
 
 const BOARD_INDENT: &str = "    ";
-const BOARD_WIDTH: usize = 7;
-const BOARD_HEIGHT: usize = 6;
 const TOKEN_EMPTY: &str = "  ";  // Player tokens are 2 chars wide.
 const TOKEN_P1: &str = "🟡";
 const TOKEN_P2: &str = "🔴";
+const TOKEN_P1_WIN: &str = "🟨";  // Marks the tokens that formed the winning line.
+const TOKEN_P2_WIN: &str = "🟥";
 
 
-pub fn print_board(board: &Vec<Vec<i32>>, player: i32) {
+// The emoji board drawn on the terminal, redrawn in place on every turn. The prompt only lists
+// the commands the player at the keyboard may use.
+pub struct TerminalRenderer {
+    hints: bool,  // Whether the hint command is allowed.
+    takebacks: bool,  // Whether undo, redo and load are allowed.
+}
+
+
+impl TerminalRenderer {
+    pub fn new(hints: bool, takebacks: bool) -> Self {
+        return TerminalRenderer { hints, takebacks };
+    }
+}
+
+
+impl Renderer for TerminalRenderer {
+    fn board_updated(&mut self, game: &ConnectFourGame) {
+        print_board(&game.board(), &game.config());
+    }
+
+
+    fn clock_updated(&mut self, times: [Duration; 2]) {
+        print!("{}", BOARD_INDENT);
+        println!("{} {}   {} {}", token(Player::One), clock::format_time(times[0]),
+                 token(Player::Two), clock::format_time(times[1]));
+        println!();
+    }
+
+
+    fn prompt(&mut self, game: &ConnectFourGame) {
+        print_instructions(&game.config(), game.active_player(), self.hints, self.takebacks);
+        let opponent: Player = game.active_player().other();
+        if game.draw_offer() == Some(opponent) {
+            println!("{} offers a draw. Enter [accept] to agree to it.", token(opponent));
+        }
+    }
+
+
+    fn invalid_move(&mut self, col: usize, error: MoveError) {
+        println!("{}", render::describe_move_error(error, col));
+    }
+
+
+    fn game_over(&mut self, game: &ConnectFourGame, state: GameState) {
+        // Reprint the board, highlighting the winning line if someone won.
+        match state {
+            GameState::Win(winner) => {
+                print_end(&game.board(), &game.config(), &game.winning_line());
+                println!("{} wins!", token(winner));
+            }
+            GameState::OutOfTime(loser) => {
+                print_end(&game.board(), &game.config(), &[]);
+                println!("{} ran out of time. {} wins!", token(loser), token(loser.other()));
+            }
+            GameState::Resigned(loser) => {
+                print_end(&game.board(), &game.config(), &[]);
+                println!("{} resigns. {} wins!", token(loser), token(loser.other()));
+            }
+            GameState::DrawAgreed => {
+                print_end(&game.board(), &game.config(), &[]);
+                println!("Draw agreed.");
+            }
+            GameState::Aborted => {
+                print_end(&game.board(), &game.config(), &[]);
+                println!("Game aborted.");
+            }
+            GameState::Tie | GameState::InProgress => {
+                print_end(&game.board(), &game.config(), &[]);
+                println!("It's a tie!");
+            }
+        }
+        if let Ok(moves) = notation::serialize(game) {
+            println!("Moves: {moves}");
+        }
+        println!("Thanks for playing.");
+    }
+
+
+    fn message(&mut self, text: &str) {
+        println!("{text}");
+    }
+}
+
+
+pub fn print_board(board: &[Vec<Player>], config: &GameConfig) {
     clear_board();
-    for row in (0..BOARD_HEIGHT).rev() {
-        print_row(board, row);
-        println!("");
-    }
-    print_bottom_row();
-    print_command_row();
-    println!("");
-    print_instructions(player);
+    for row in (0..config.height()).rev() {
+        print_row(board, row, &[]);
+        println!();
+    }
+    print_bottom_row(config);
+    print_command_row(config);
+    println!();
 }
 
 
-pub fn print_end(board: &Vec<Vec<i32>>) {
+// Print the final board, highlighting the given (col, row) cells of the winning line.
+pub fn print_end(board: &[Vec<Player>], config: &GameConfig, winning_line: &[(usize, usize)]) {
     clear_board();
-    for row in (0..BOARD_HEIGHT).rev() {
-        print_row(board, row);
-        println!("");
+    for row in (0..config.height()).rev() {
+        print_row(board, row, winning_line);
+        println!();
     }
-    print_bottom_row();
+    print_bottom_row(config);
 }
 
 
-fn token(player: i32) -> String {
+pub fn token(player: Player) -> String {
     return match player {
-        1 => String::from(TOKEN_P1),
-        2 => String::from(TOKEN_P2),
-        _ => String::from(TOKEN_EMPTY),
+        Player::One => String::from(TOKEN_P1),
+        Player::Two => String::from(TOKEN_P2),
     }
 }
 
 
-fn print_bottom_row() {
+// Return the highlighted token used for the player's tokens in the winning line.
+fn winning_token(player: Player) -> String {
+    return match player {
+        Player::One => String::from(TOKEN_P1_WIN),
+        Player::Two => String::from(TOKEN_P2_WIN),
+    }
+}
+
+
+// Each column is a 2-char token followed by a 3-char " | " separator.
+fn print_bottom_row(config: &GameConfig) {
     print!("{}", BOARD_INDENT);
-    println!(" ==================================== ");
+    println!(" {} ", "=".repeat(5 * config.width() + 1));
 }
 
 
-fn print_command_row() {
+fn print_command_row(config: &GameConfig) {
     print!("{}", BOARD_INDENT);
-    println!("   1    2    3    4    5    6    7  ");
+    print!("   ");
+    for col in 1..=config.width() {
+        print!("{:<2}   ", col);
+    }
+    println!();
 }
 
 
-fn print_instructions(player: i32) {
+fn print_instructions(config: &GameConfig, player: Player, hints: bool, takebacks: bool) {
     println!("Choose a column - [1] through [{}] - and press [Enter] to play that column. ",
-             BOARD_WIDTH);
-    println!("Enter [q] to quit.");
+             config.width());
+    let mut commands: Vec<&str> = Vec::new();
+    if hints {
+        commands.push("[h] for a hint");
+    }
+    if takebacks {
+        commands.extend(["[u] to undo", "[r] to redo"]);
+    }
+    let (last, others) = ("[q] to abort the game", commands.join(", "));
+    if others.is_empty() {
+        println!("Enter {last}.");
+    } else {
+        println!("Enter {others} or {last}.");
+    }
+    println!("Enter [resign] to resign or [draw] to offer a draw.");
+    if takebacks {
+        println!("Enter [save <file>] or [load <file>] to save or resume a game.");
+    } else {
+        println!("Enter [save <file>] to save the game.");
+    }
     println!("It's {}'s turn.", token(player));
 }
 
 
-fn print_row(board: &Vec<Vec<i32>>, row: usize) {
+fn print_row(board: &[Vec<Player>], row: usize, highlighted: &[(usize, usize)]) {
     print!("{}", BOARD_INDENT);
     print!(" | ");
-    for col in 0..BOARD_WIDTH {
-        if board[col].len() <= row {
+    for (col, column) in board.iter().enumerate() {
+        if column.len() <= row {
             print!("{}", String::from(TOKEN_EMPTY));
+        } else if highlighted.contains(&(col, row)) {
+            print!("{}", winning_token(column[row]));
         } else {
-            print!("{}", token(board[col][row]));
+            print!("{}", token(column[row]));
         }
         print!(" | ");
     }
@@ -0,0 +1,530 @@
+// A line-based text protocol for driving a Connect-4 engine, in the style of chess's UCI.
+//
+// A front end (a GUI, a tournament runner, or this game) sends one command per line to the
+// engine's stdin, and the engine answers on its stdout:
+//
+//   c4                           The engine replies `id name <name>` and then `c4ok`.
+//   isready                      The engine replies `readyok` once it has handled every
+//                                earlier command.
+//   newgame [W H N]              Start a new game on the standard board, or on a W x H board
+//                                with N in a row to win.
+//   position [moves C ...]       Set the position to the given moves from the empty board,
+//                                as 1-indexed columns separated by spaces.
+//   go [depth D] [movetime MS]   Search the position. The engine replies `info depth D score S`
+//                                for every depth it finishes and then `bestmove C score S`, or
+//                                `bestmove none` if the game is over. With a move time the
+//                                engine searches deeper until the time is about to run out,
+//                                and with neither limit it searches a default depth.
+//   quit                         Exit.
+//
+// Scores are from the point of view of the player to move; positive scores favour them, and
+// scores above 500000 mean they can force a win. A command the engine can't handle is answered
+// with `error <message>` and otherwise ignored, and blank lines are ignored.
+
+use crate::game::ConnectFourGame;
+use crate::game::GameConfig;
+use crate::move_source::GameView;
+use crate::move_source::Move;
+use crate::move_source::MoveSource;
+use crate::negamax::BRANCHING_FACTOR;
+use crate::negamax::NegamaxPlayer;
+use crate::position::Position;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::process::Child;
+use std::process::ChildStdin;
+use std::process::ChildStdout;
+use std::process::Command as Process;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+
+
+const DEFAULT_DEPTH: usize = 8;
+const ENGINE_NAME: &str = "rust-connect-4";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);  // How long an engine has to start.
+
+
+// How long a search may take. With no limits the default depth is searched.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(Default)]
+#[derive(PartialEq)]
+pub struct Limits {
+    pub depth: Option<usize>,
+    pub movetime: Option<Duration>,
+}
+
+
+// A command sent to an engine.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Command {
+    Hello,
+    IsReady,
+    NewGame(GameConfig),
+    Position(Vec<usize>),  // The moves from the empty board, as 0-indexed columns.
+    Go(Limits),
+    Quit,
+}
+
+
+// The answering side of the protocol, which plays using negamax search.
+pub struct Engine {
+    game: ConnectFourGame,
+}
+
+
+// An engine running in another process, used as a move source.
+pub struct ExternalEngine {
+    child: Child,
+    input: ChildStdin,
+    output: Receiver<String>,  // The engine's output lines, read on a thread so waits can time out.
+    limits: Limits,
+}
+
+
+// Parse a command line, or return a message explaining why it couldn't be parsed.
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let numbers = |words: &[&str]| -> Result<Vec<usize>, String> {
+        return words.iter()
+                .map(|word| word.parse::<usize>().map_err(|_| format!("not a number: {word}")))
+                .collect();
+    };
+    return match words.as_slice() {
+        ["c4"] => Ok(Command::Hello),
+        ["isready"] => Ok(Command::IsReady),
+        ["newgame"] => Ok(Command::NewGame(GameConfig::default())),
+        ["newgame", size @ ..] if size.len() == 3 => {
+            let size: Vec<usize> = numbers(size)?;
+            let config: GameConfig = GameConfig::new(size[0], size[1], size[2])
+                    .map_err(|error| format!("invalid board: {error:?}"))?;
+            Ok(Command::NewGame(config))
+        }
+        ["position"] => Ok(Command::Position(Vec::new())),
+        ["position", "moves", moves @ ..] => {
+            let moves: Vec<usize> = numbers(moves)?;
+            if moves.contains(&0) {
+                return Err(String::from("columns are numbered from 1"));
+            }
+            Ok(Command::Position(moves.iter().map(|col| col - 1).collect()))
+        }
+        ["go", limits @ ..] => Ok(Command::Go(parse_limits(limits)?)),
+        ["quit"] => Ok(Command::Quit),
+        _ => Err(format!("unknown command: {line}")),
+    };
+}
+
+
+fn parse_limits(words: &[&str]) -> Result<Limits, String> {
+    let mut limits: Limits = Limits::default();
+    for pair in words.chunks(2) {
+        let value: usize = match pair.get(1).map(|value| value.parse::<usize>()) {
+            Some(Ok(value)) => value,
+            _ => return Err(format!("missing or invalid value for {}", pair[0])),
+        };
+        match pair[0] {
+            "depth" => limits.depth = Some(value.max(1)),
+            "movetime" => limits.movetime = Some(Duration::from_millis(value as u64)),
+            other => return Err(format!("unknown limit: {other}")),
+        }
+    }
+    return Ok(limits);
+}
+
+
+// Write the command as a line of the protocol.
+pub fn format_command(command: &Command) -> String {
+    return match command {
+        Command::Hello => String::from("c4"),
+        Command::IsReady => String::from("isready"),
+        Command::NewGame(config) =>
+                format!("newgame {} {} {}", config.width(), config.height(), config.win_length()),
+        Command::Position(moves) if moves.is_empty() => String::from("position"),
+        Command::Position(moves) => {
+            let moves: Vec<String> = moves.iter().map(|col| (col + 1).to_string()).collect();
+            format!("position moves {}", moves.join(" "))
+        }
+        Command::Go(limits) => {
+            let mut line: String = String::from("go");
+            if let Some(depth) = limits.depth {
+                line += &format!(" depth {depth}");
+            }
+            if let Some(movetime) = limits.movetime {
+                line += &format!(" movetime {}", movetime.as_millis());
+            }
+            line
+        }
+        Command::Quit => String::from("quit"),
+    };
+}
+
+
+// Parse a `bestmove` reply into the 0-indexed column and score, or None if the line isn't a
+// best move or the engine had no move.
+pub fn parse_best_move(line: &str) -> Option<(usize, i32)> {
+    return match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+        ["bestmove", col, "score", score] => {
+            let col: usize = col.parse::<usize>().ok().filter(|col| *col > 0)?;
+            Some((col - 1, score.parse::<i32>().ok()?))
+        }
+        _ => None,
+    };
+}
+
+
+// Answer commands read from the input on the output, until a quit command or the end of the
+// input.
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut engine: Engine = Engine::new();
+    for line in input.lines() {
+        let line: String = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let replies: Vec<String> = match parse_command(&line) {
+            Ok(Command::Quit) => return Ok(()),
+            Ok(command) => engine.respond(command),
+            Err(message) => vec![format!("error {message}")],
+        };
+        for reply in replies {
+            writeln!(output, "{reply}")?;
+        }
+        output.flush()?;
+    }
+    return Ok(());
+}
+
+
+impl Engine {
+    pub fn new() -> Self {
+        return Engine { game: ConnectFourGame::default() };
+    }
+
+
+    pub fn game(&self) -> &ConnectFourGame {
+        return &self.game;
+    }
+
+
+    // Handle a command and return the reply lines.
+    pub fn respond(&mut self, command: Command) -> Vec<String> {
+        return match command {
+            Command::Hello => vec![format!("id name {ENGINE_NAME}"), String::from("c4ok")],
+            Command::IsReady => vec![String::from("readyok")],
+            Command::NewGame(config) => {
+                self.game = ConnectFourGame::new(config);
+                Vec::new()
+            }
+            Command::Position(moves) => {
+                let mut game: ConnectFourGame = ConnectFourGame::new(self.game.config());
+                for col in &moves {
+                    if let Err(error) = game.play_move(game.active_player(), *col) {
+                        return vec![format!("error illegal move {}: {error:?}", col + 1)];
+                    }
+                }
+                self.game = game;
+                Vec::new()
+            }
+            Command::Go(limits) => self.go(limits),
+            Command::Quit => Vec::new(),
+        };
+    }
+
+
+    // Search deeper and deeper until the depth limit or until the next depth would likely
+    // overrun the move time, reporting each depth as it finishes.
+    fn go(&self, limits: Limits) -> Vec<String> {
+        let position: &Position = self.game.position();
+        if self.game.is_over() {
+            return vec![String::from("bestmove none")];
+        }
+        let cells: usize = position.config().width() * position.config().height();
+        let max_depth: usize = match limits {
+            Limits { depth: Some(depth), .. } => depth,
+            Limits { movetime: Some(_), .. } => cells - position.moves(),
+            _ => DEFAULT_DEPTH,
+        };
+        let start: Instant = Instant::now();
+        let mut replies: Vec<String> = Vec::new();
+        let mut best: Option<(usize, i32)> = None;
+        for depth in 1..=max_depth {
+            let depth_start: Instant = Instant::now();
+            best = NegamaxPlayer::new(depth).search(position);
+            if let Some((_, score)) = best {
+                replies.push(format!("info depth {depth} score {score}"));
+            }
+            let out_of_time: bool = limits.movetime.is_some_and(|movetime| {
+                start.elapsed() + depth_start.elapsed() * BRANCHING_FACTOR >= movetime
+            });
+            if out_of_time {
+                break;
+            }
+        }
+        replies.push(match best {
+            Some((col, score)) => format!("bestmove {} score {score}", col + 1),
+            None => String::from("bestmove none"),
+        });
+        return replies;
+    }
+}
+
+
+impl Default for Engine {
+    fn default() -> Self {
+        return Engine::new();
+    }
+}
+
+
+impl ExternalEngine {
+    // Start the engine program with the given arguments, and set it up for a new game with the
+    // given config. Every move is searched within the given limits. An engine that doesn't
+    // answer the handshake in time is stopped and reported as an error.
+    pub fn spawn(program: &str, args: &[String], config: GameConfig, limits: Limits)
+            -> io::Result<Self> {
+        return ExternalEngine::spawn_within(program, args, config, limits, STARTUP_TIMEOUT);
+    }
+
+
+    fn spawn_within(program: &str, args: &[String], config: GameConfig, limits: Limits,
+                    startup_timeout: Duration) -> io::Result<Self> {
+        let mut child: Child = Process::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+        let input: ChildStdin = child.stdin.take().expect("stdin is piped");
+        let stdout: ChildStdout = child.stdout.take().expect("stdout is piped");
+        let (sender, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine: ExternalEngine = ExternalEngine { child, input, output, limits };
+        if let Err(error) = engine.handshake(config, Instant::now() + startup_timeout) {
+            let _ = engine.child.kill();
+            return Err(error);
+        }
+        return Ok(engine);
+    }
+
+
+    fn handshake(&mut self, config: GameConfig, deadline: Instant) -> io::Result<()> {
+        self.send(&Command::Hello)?;
+        self.read_until(|line| line == "c4ok", Some(deadline))?;
+        self.send(&Command::NewGame(config))?;
+        self.send(&Command::IsReady)?;
+        self.read_until(|line| line == "readyok", Some(deadline))?;
+        return Ok(());
+    }
+
+
+    // Ask the engine for its best move in the given game, as a 0-indexed column and score.
+    pub fn best_move(&mut self, game: &ConnectFourGame) -> io::Result<Option<(usize, i32)>> {
+        return self.search(game, self.limits, None);
+    }
+
+
+    fn search(&mut self, game: &ConnectFourGame, limits: Limits, deadline: Option<Instant>)
+            -> io::Result<Option<(usize, i32)>> {
+        self.send(&Command::Position(game.history().to_vec()))?;
+        self.send(&Command::Go(limits))?;
+        let reply: String = self.read_until(|line| line.starts_with("bestmove"), deadline)?;
+        return Ok(parse_best_move(&reply));
+    }
+
+
+    fn send(&mut self, command: &Command) -> io::Result<()> {
+        writeln!(self.input, "{}", format_command(command))?;
+        return self.input.flush();
+    }
+
+
+    // Read lines from the engine until one matches, and return it. Give up once the deadline,
+    // if any, has passed.
+    fn read_until(&mut self, matches: impl Fn(&str) -> bool, deadline: Option<Instant>)
+            -> io::Result<String> {
+        loop {
+            let received: Result<String, RecvTimeoutError> = match deadline {
+                Some(deadline) => self.output
+                        .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self.output.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let line: String = match received {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Err(io::Error::new(
+                        io::ErrorKind::TimedOut, "the engine ran out of time")),
+                Err(RecvTimeoutError::Disconnected) => return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof, "the engine exited")),
+            };
+            if matches(line.trim()) {
+                return Ok(String::from(line.trim()));
+            }
+        }
+    }
+}
+
+
+// The engine quits with the game. If it can't be asked to, it's stopped.
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        if self.send(&Command::Quit).is_err() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+
+// An engine that fails or answers with a move that can't be played forfeits the game, as if it
+// resigned. On a clock, the engine is only waited for until its time runs out, and is then
+// stopped.
+impl MoveSource for ExternalEngine {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        // On a clock, the engine thinks for no longer than the clock allows.
+        let mut limits: Limits = self.limits;
+        if let Some(think_time) = view.think_time() {
+            limits.movetime = Some(limits.movetime.map_or(think_time, |t| t.min(think_time)));
+        }
+        return match self.search(view.game(), limits, view.deadline()) {
+            Ok(Some((col, _))) if view.position().can_play(col) => Move::Play(col),
+            Err(error) if error.kind() == io::ErrorKind::TimedOut => {
+                let _ = self.child.kill();
+                Move::Resign
+            }
+            _ => Move::Resign,
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation;
+
+    fn replies(commands: &str) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        run(commands.as_bytes(), &mut output).unwrap();
+        return String::from_utf8(output).unwrap();
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("newgame 9 7 5"),
+                   Ok(Command::NewGame(GameConfig::new(9, 7, 5).unwrap())));
+        assert_eq!(parse_command("position moves 4 4 5"), Ok(Command::Position(vec![3, 3, 4])));
+        assert_eq!(parse_command("go depth 3 movetime 200"), Ok(Command::Go(Limits {
+            depth: Some(3),
+            movetime: Some(Duration::from_millis(200)),
+        })));
+        assert!(parse_command("position moves 0").is_err());
+        assert!(parse_command("go depth").is_err());
+        assert!(parse_command("fly").is_err());
+    }
+
+    #[test]
+    fn test_format_command_round_trips() {
+        let commands = [
+            Command::Hello,
+            Command::NewGame(GameConfig::new(8, 7, 4).unwrap()),
+            Command::Position(Vec::new()),
+            Command::Position(vec![3, 3, 4]),
+            Command::Go(Limits { depth: Some(5), movetime: None }),
+            Command::Go(Limits { depth: None, movetime: Some(Duration::from_millis(50)) }),
+            Command::Quit,
+        ];
+        for command in commands {
+            assert_eq!(parse_command(&format_command(&command)), Ok(command));
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let output = replies("c4\nisready\nnewgame\n\
+                              position moves 1 2 1 2 1 2\ngo depth 2\nquit\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[..3], [&format!("id name {ENGINE_NAME}"), "c4ok", "readyok"]);
+        assert_eq!(lines[3..5], ["info depth 1 score 999994", "info depth 2 score 999994"]);
+        assert_eq!(parse_best_move(lines[5]), Some((0, 999994)));
+    }
+
+    #[test]
+    fn test_errors_and_game_over() {
+        let output = replies("position moves 1 1 1 1 1 1 1\nbogus\n\
+                              position moves 1 2 1 2 1 2 1\ngo\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("error illegal move 1"));
+        assert!(lines[1].starts_with("error unknown command"));
+        assert_eq!(lines[2], "bestmove none");
+    }
+
+    #[test]
+    fn test_movetime() {
+        let mut engine = Engine::new();
+        engine.respond(Command::Position(vec![3, 3]));
+        let start = Instant::now();
+        let limits = Limits { depth: None, movetime: Some(Duration::from_millis(100)) };
+        let replies = engine.respond(Command::Go(limits));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(replies.last().unwrap().starts_with("bestmove"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_external_engine() {
+        // A stand-in engine that always plays column 4.
+        let script = "while read line; do case $line in \
+                      c4) echo c4ok;; isready) echo readyok;; \
+                      go*) echo 'bestmove 4 score 7';; quit) exit;; esac; done";
+        let args = [String::from("-c"), String::from(script)];
+        let limits = Limits { depth: Some(3), movetime: None };
+        let mut engine = ExternalEngine::spawn("sh", &args, GameConfig::default(), limits).unwrap();
+        let game = notation::parse(GameConfig::default(), "12").unwrap();
+        assert_eq!(engine.best_move(&game).unwrap(), Some((3, 7)));
+        assert_eq!(engine.choose_move(&GameView::new(&game)), Move::Play(3));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_external_engine_that_never_starts() {
+        let args = [String::from("-c"), String::from("sleep 60")];
+        let config = GameConfig::default();
+        let limits = Limits::default();
+        let start = Instant::now();
+        let engine = ExternalEngine::spawn_within("sh", &args, config, limits,
+                                                  Duration::from_millis(50));
+        assert_eq!(engine.err().map(|error| error.kind()), Some(io::ErrorKind::TimedOut));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_external_engine_not_waited_for_past_deadline() {
+        // A stand-in engine that never answers a search.
+        let script = "while read line; do case $line in \
+                      c4) echo c4ok;; isready) echo readyok;; quit) exit;; esac; done";
+        let args = [String::from("-c"), String::from(script)];
+        let config = GameConfig::default();
+        let mut engine = ExternalEngine::spawn("sh", &args, config, Limits::default()).unwrap();
+        let game = ConnectFourGame::new(config);
+        let view = GameView::with_clock(&game, Duration::ZERO, Duration::from_millis(50));
+        let started = Instant::now();
+        engine.choose_move(&view);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,662 @@
+// Game records with metadata, in a format modelled on chess PGN.
+//
+// A record is a block of headers followed by the move list, for example:
+//
+//     [Event "Casual game"]
+//     [Date "2023.07.23"]
+//     [Player1 "Alice"]
+//     [Player2 "Bob"]
+//     [Variant "7x6 connect 4"]
+//     [TimeControl "-"]
+//     [Result "1-0"]
+//
+//     1. 4 4 2. 5 {Threatens both sides.} 3 3. 6 2 4. 7 1-0
+//
+// Each header is `[Name "value"]`, with `\"`, `\\` and `\n` escaping quotes, backslashes and
+// newlines in the value. The `Variant` header gives the board as
+// `<width>x<height> connect <win length>` and defaults to the standard board when missing. In
+// the move list, `N.` starts the Nth round, each move is its 1-indexed column number and a
+// `{comment}` right after a move is attached to it, with `\{`, `\}`, `\\` and `\n` escaping
+// braces, backslashes and newlines in it. The list ends with the result: `1-0` (player 1 won),
+// `0-1` (player 2 won), `1/2-1/2` (a draw) or `*` (unfinished). `TimeControl` gives the clock
+// settings as described in `clock.rs`.
+// A game that didn't end on the board has a `Termination` header saying how it ended instead:
+// `time forfeit`, `resignation`, `draw agreement` or `abandoned`. An archive is any number of
+// records separated by blank lines.
+
+use crate::game::ConnectFourGame;
+use crate::game::GameConfig;
+use crate::game::GameState;
+use crate::game::MoveError;
+use crate::game::Player;
+use std::fmt;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+
+pub const HEADER_DATE: &str = "Date";
+pub const HEADER_EVENT: &str = "Event";
+pub const HEADER_PLAYER1: &str = "Player1";
+pub const HEADER_PLAYER2: &str = "Player2";
+pub const HEADER_RESULT: &str = "Result";
+pub const HEADER_TERMINATION: &str = "Termination";
+pub const HEADER_TIME_CONTROL: &str = "TimeControl";
+pub const HEADER_VARIANT: &str = "Variant";
+
+
+// The outcome written at the end of a record.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum RecordResult {
+    Win(Player),
+    Draw,
+    Unfinished,
+}
+
+
+impl RecordResult {
+    pub fn from_state(state: GameState) -> Self {
+        return match state {
+            GameState::Win(player) => RecordResult::Win(player),
+            GameState::OutOfTime(player) | GameState::Resigned(player) =>
+                    RecordResult::Win(player.other()),
+            GameState::Tie | GameState::DrawAgreed => RecordResult::Draw,
+            GameState::InProgress | GameState::Aborted => RecordResult::Unfinished,
+        };
+    }
+
+
+    pub fn token(self) -> &'static str {
+        return match self {
+            RecordResult::Win(Player::One) => "1-0",
+            RecordResult::Win(Player::Two) => "0-1",
+            RecordResult::Draw => "1/2-1/2",
+            RecordResult::Unfinished => "*",
+        };
+    }
+
+
+    fn from_token(token: &str) -> Option<Self> {
+        return match token {
+            "1-0" => Some(RecordResult::Win(Player::One)),
+            "0-1" => Some(RecordResult::Win(Player::Two)),
+            "1/2-1/2" => Some(RecordResult::Draw),
+            "*" => Some(RecordResult::Unfinished),
+            _ => None,
+        };
+    }
+}
+
+
+// One move of a record: a 0-indexed column and an optional comment.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct RecordedMove {
+    pub column: usize,
+    pub comment: Option<String>,
+}
+
+
+// The reasons a record can fail to read or replay.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum RecordError {
+    // The given line (1-indexed) of the text is malformed.
+    Malformed { line: usize, message: String },
+    // The Variant header doesn't describe a valid board.
+    InvalidVariant(String),
+    // The move with the given move number (1-indexed) can't be played.
+    IllegalMove { move_number: usize, column: usize, error: MoveError },
+}
+
+
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct GameRecord {
+    headers: Vec<(String, String)>,  // In the order they are written.
+    pub moves: Vec<RecordedMove>,
+    result: RecordResult,  // Kept in step with the Result header.
+}
+
+
+impl GameRecord {
+    // Return a record of the given game, dated today, with the standard headers filled in.
+    pub fn from_game(game: &ConnectFourGame) -> Self {
+        let mut record: GameRecord = GameRecord {
+            headers: Vec::new(),
+            moves: game.history().iter()
+                    .map(|col| RecordedMove { column: *col, comment: None })
+                    .collect(),
+            result: RecordResult::Unfinished,
+        };
+        record.set_header(HEADER_EVENT, "Casual game");
+        record.set_header(HEADER_DATE, &current_date());
+        record.set_header(HEADER_PLAYER1, "Player 1");
+        record.set_header(HEADER_PLAYER2, "Player 2");
+        record.set_header(HEADER_VARIANT, &format_variant(&game.config()));
+        record.set_header(HEADER_TIME_CONTROL, "-");
+        record.set_result(RecordResult::from_state(game.state()));
+        if let Some(termination) = termination(game.state()) {
+            record.set_header(HEADER_TERMINATION, termination);
+        }
+        return record;
+    }
+
+
+    pub fn result(&self) -> RecordResult {
+        return self.result;
+    }
+
+
+    // Set the result, updating the Result header to match.
+    pub fn set_result(&mut self, result: RecordResult) {
+        self.result = result;
+        self.set_header(HEADER_RESULT, result.token());
+    }
+
+
+    // Return the value of the given header, if present.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        return self.headers.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str());
+    }
+
+
+    // Return every header in order.
+    pub fn headers(&self) -> &[(String, String)] {
+        return &self.headers;
+    }
+
+
+    // Set the given header, replacing its value if it is already present.
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        match self.headers.iter_mut().find(|(key, _)| key == name) {
+            Some(header) => header.1 = String::from(value),
+            None => self.headers.push((String::from(name), String::from(value))),
+        }
+    }
+
+
+    // Return the game config described by the Variant header.
+    pub fn config(&self) -> Result<GameConfig, RecordError> {
+        return match self.header(HEADER_VARIANT) {
+            Some(variant) => parse_variant(variant),
+            None => Ok(GameConfig::default()),
+        };
+    }
+
+
+    // Replay the recorded moves, checking each one against the rules, and end the game the way
+    // the Termination header says if it didn't end on the board. Other terminations are
+    // ignored.
+    pub fn replay(&self) -> Result<ConnectFourGame, RecordError> {
+        let mut game: ConnectFourGame = ConnectFourGame::new(self.config()?);
+        for (i, recorded) in self.moves.iter().enumerate() {
+            game.play_move(game.active_player(), recorded.column)
+                    .map_err(|error| RecordError::IllegalMove {
+                        move_number: i + 1, column: recorded.column + 1, error })?;
+        }
+        let player: Player = game.active_player();
+        let consistent: bool = match (self.header(HEADER_TERMINATION), self.result) {
+            (Some("time forfeit"), RecordResult::Win(winner)) if winner == player.other() =>
+                    game.lose_on_time() == GameState::OutOfTime(player),
+            (Some("resignation"), RecordResult::Win(winner)) =>
+                    game.resign(winner.other()) == GameState::Resigned(winner.other()),
+            (Some("draw agreement"), RecordResult::Draw) => {
+                game.offer_draw(player);
+                game.accept_draw(player.other())
+            }
+            (Some("abandoned"), RecordResult::Unfinished) =>
+                    game.abort() == GameState::Aborted,
+            (Some("time forfeit" | "resignation" | "draw agreement" | "abandoned"), _) => false,
+            _ => true,
+        };
+        if !consistent {
+            return Err(malformed(0, "the termination doesn't match the moves and result"));
+        }
+        return Ok(game);
+    }
+
+
+    // Read one record.
+    pub fn read(text: &str) -> Result<GameRecord, RecordError> {
+        let mut records: Vec<GameRecord> = read_archive(text)?;
+        if records.len() != 1 {
+            return Err(malformed(0, &format!("expected 1 record, found {}", records.len())));
+        }
+        return Ok(records.remove(0));
+    }
+}
+
+
+impl fmt::Display for GameRecord {
+    // Write the record in the record format, ending with a blank line so records can be
+    // appended to an archive one after another.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in &self.headers {
+            writeln!(f, "[{} \"{}\"]", name, escape(value, &['"']))?;
+        }
+        writeln!(f)?;
+        let mut tokens: Vec<String> = Vec::new();
+        for (i, recorded) in self.moves.iter().enumerate() {
+            if i % 2 == 0 {
+                tokens.push(format!("{}.", i / 2 + 1));
+            }
+            tokens.push((recorded.column + 1).to_string());
+            if let Some(comment) = &recorded.comment {
+                tokens.push(format!("{{{}}}", escape(comment, &['{', '}'])));
+            }
+        }
+        tokens.push(String::from(self.result.token()));
+        writeln!(f, "{}", tokens.join(" "))?;
+        return writeln!(f);
+    }
+}
+
+
+// Read every record in the given archive.
+pub fn read_archive(text: &str) -> Result<Vec<GameRecord>, RecordError> {
+    let mut records: Vec<GameRecord> = Vec::new();
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut movetext: String = String::new();
+    let mut movetext_line: usize = 0;
+    for (i, raw_line) in text.lines().enumerate() {
+        let line: &str = raw_line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            if !movetext.is_empty() {
+                return Err(malformed(i + 1, "header after the move list"));
+            }
+            headers.push(parse_header(line, i + 1)?);
+            continue;
+        }
+        if movetext.is_empty() {
+            movetext_line = i + 1;
+        }
+        movetext.push_str(line);
+        movetext.push(' ');
+        // The result token ends the record.
+        if line.split_whitespace().last().and_then(RecordResult::from_token).is_some()
+                && !in_comment(&movetext) {
+            let (moves, result) = parse_movetext(&movetext, movetext_line)?;
+            let mut record: GameRecord = GameRecord { headers, moves, result };
+            record.set_result(result);  // The move list's result wins over the header.
+            records.push(record);
+            headers = Vec::new();
+            movetext = String::new();
+        }
+    }
+    if !headers.is_empty() || !movetext.is_empty() {
+        return Err(malformed(text.lines().count(), "record doesn't end with a result"));
+    }
+    return Ok(records);
+}
+
+
+// Return today's date (UTC) in the record format, YYYY.MM.DD.
+pub fn current_date() -> String {
+    let seconds: u64 = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    return format!("{:04}.{:02}.{:02}", year, month, day);
+}
+
+
+// Convert a count of days since 1970-01-01 to a (year, month, day) date in the Gregorian
+// calendar, using Howard Hinnant's days-to-civil algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z: i64 = days + 719468;
+    let era: i64 = z.div_euclid(146097);
+    let day_of_era: i64 = z.rem_euclid(146097);
+    let year_of_era: i64 =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;  // March is 0.
+    let day: u32 = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month: u32 = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year: i64 = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, day);
+}
+
+
+fn format_variant(config: &GameConfig) -> String {
+    return format!("{}x{} connect {}", config.width(), config.height(), config.win_length());
+}
+
+
+// Return the Termination header for a game that ended in the given state, if it didn't end on
+// the board.
+fn termination(state: GameState) -> Option<&'static str> {
+    return match state {
+        GameState::OutOfTime(_) => Some("time forfeit"),
+        GameState::Resigned(_) => Some("resignation"),
+        GameState::DrawAgreed => Some("draw agreement"),
+        GameState::Aborted => Some("abandoned"),
+        GameState::InProgress | GameState::Win(_) | GameState::Tie => None,
+    };
+}
+
+
+fn parse_variant(variant: &str) -> Result<GameConfig, RecordError> {
+    let invalid = || RecordError::InvalidVariant(String::from(variant));
+    let (size, win_length) = variant.split_once(" connect ").ok_or_else(invalid)?;
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let parse = |value: &str| value.trim().parse::<usize>().map_err(|_| invalid());
+    return GameConfig::new(parse(width)?, parse(height)?, parse(win_length)?)
+            .map_err(|_| invalid());
+}
+
+
+// Parse a `[Name "value"]` header line.
+fn parse_header(line: &str, line_number: usize) -> Result<(String, String), RecordError> {
+    let inner: &str = line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+            .ok_or_else(|| malformed(line_number, "header must be wrapped in [ ]"))?;
+    let (name, quoted) = inner.split_once(' ')
+            .ok_or_else(|| malformed(line_number, "header needs a name and a value"))?;
+    let value: &str = quoted.trim().strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+            .ok_or_else(|| malformed(line_number, "header value must be quoted"))?;
+    return Ok((String::from(name), unescape(value)));
+}
+
+
+// Return the text with a backslash before each backslash and each of the given characters, and
+// with newlines written as `\n`, so that it fits on one line.
+fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped: String = String::new();
+    for c in text.chars() {
+        if c == '\n' {
+            escaped.push_str("\\n");
+            continue;
+        }
+        if c == '\\' || special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    return escaped;
+}
+
+
+// Undo `escape`.
+fn unescape(text: &str) -> String {
+    let mut unescaped: String = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some(escaped) => escaped,
+                None => '\\',
+            },
+            _ => c,
+        });
+    }
+    return unescaped;
+}
+
+
+// Return whether the given move text ends inside an unclosed comment. Comments don't nest, and
+// escaped braces don't open or close them.
+fn in_comment(movetext: &str) -> bool {
+    let mut inside: bool = false;
+    let mut chars = movetext.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if inside => {
+                chars.next();
+            }
+            '{' => inside = true,
+            '}' => inside = false,
+            _ => {},
+        }
+    }
+    return inside;
+}
+
+
+// Return the byte index of the unescaped `}` that closes the comment the text is inside.
+fn comment_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '}' => return Some(i),
+            _ => {},
+        }
+    }
+    return None;
+}
+
+
+// Parse a move list ending with a result token.
+fn parse_movetext(movetext: &str, line: usize)
+        -> Result<(Vec<RecordedMove>, RecordResult), RecordError> {
+    let mut moves: Vec<RecordedMove> = Vec::new();
+    let mut rest: &str = movetext.trim();
+    loop {
+        if let Some(after_brace) = rest.strip_prefix('{') {
+            let end: usize = comment_end(after_brace)
+                    .ok_or_else(|| malformed(line, "unclosed comment"))?;
+            let last: &mut RecordedMove = moves.last_mut()
+                    .ok_or_else(|| malformed(line, "comment before the first move"))?;
+            last.comment = Some(unescape(after_brace[..end].trim()));
+            rest = after_brace[end + 1..].trim_start();
+            continue;
+        }
+        let (token, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        rest = after.trim_start();
+        if let Some(result) = RecordResult::from_token(token) {
+            if !rest.is_empty() {
+                return Err(malformed(line, "moves after the result"));
+            }
+            return Ok((moves, result));
+        }
+        if token.ends_with('.') && token[..token.len() - 1].parse::<usize>().is_ok() {
+            continue;  // A round number.
+        }
+        match token.parse::<usize>() {
+            Ok(column) if column > 0 => {
+                moves.push(RecordedMove { column: column - 1, comment: None });
+            }
+            _ => return Err(malformed(line, &format!("unexpected token {token}"))),
+        }
+    }
+}
+
+
+fn malformed(line: usize, message: &str) -> RecordError {
+    return RecordError::Malformed { line, message: String::from(message) };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation;
+
+    const EXAMPLE: &str = "[Event \"Casual game\"]\n\
+                           [Date \"2023.07.23\"]\n\
+                           [Player1 \"Alice\"]\n\
+                           [Player2 \"Bob\"]\n\
+                           [Variant \"7x6 connect 4\"]\n\
+                           [TimeControl \"-\"]\n\
+                           [Result \"1-0\"]\n\
+                           \n\
+                           1. 4 4 2. 5 {Threatens both sides.} 3 3. 6 2 4. 7 1-0\n\
+                           \n";
+
+    #[test]
+    fn test_read_example() {
+        let record = GameRecord::read(EXAMPLE).unwrap();
+        assert_eq!(record.header(HEADER_PLAYER1), Some("Alice"));
+        assert_eq!(record.header(HEADER_PLAYER2), Some("Bob"));
+        assert_eq!(record.result(), RecordResult::Win(Player::One));
+        assert_eq!(record.moves.len(), 7);
+        assert_eq!(record.moves[2].column, 4);
+        assert_eq!(record.moves[2].comment, Some(String::from("Threatens both sides.")));
+        assert_eq!(record.replay().unwrap().state(), GameState::Win(Player::One));
+    }
+
+    #[test]
+    fn test_write_example() {
+        let record = GameRecord::read(EXAMPLE).unwrap();
+        assert_eq!(record.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn test_from_game() {
+        let game = notation::parse(GameConfig::new(9, 7, 5).unwrap(), "5544").unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(record.header(HEADER_VARIANT), Some("9x7 connect 5"));
+        assert_eq!(record.result(), RecordResult::Unfinished);
+        assert_eq!(record.config(), Ok(game.config()));
+        assert_eq!(record.replay().unwrap().history(), game.history());
+    }
+
+    #[test]
+    fn test_from_game_lost_on_time() {
+        let mut game = notation::parse(GameConfig::default(), "44").unwrap();
+        game.lose_on_time();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(record.result(), RecordResult::Win(Player::Two));
+        assert_eq!(record.header(HEADER_TERMINATION), Some("time forfeit"));
+        let replayed = GameRecord::read(&record.to_string()).unwrap().replay().unwrap();
+        assert_eq!(replayed.state(), GameState::OutOfTime(Player::One));
+    }
+
+    #[test]
+    fn test_from_game_resigned_agreed_and_aborted() {
+        let endings = [
+            (GameState::Resigned(Player::One), RecordResult::Win(Player::Two), "resignation"),
+            (GameState::DrawAgreed, RecordResult::Draw, "draw agreement"),
+            (GameState::Aborted, RecordResult::Unfinished, "abandoned"),
+        ];
+        for (state, result, termination) in endings {
+            let mut game = notation::parse(GameConfig::default(), "44").unwrap();
+            match state {
+                GameState::Resigned(player) => { game.resign(player); },
+                GameState::DrawAgreed => {
+                    game.offer_draw(Player::Two);
+                    game.accept_draw(Player::One);
+                }
+                _ => { game.abort(); },
+            }
+            let record = GameRecord::from_game(&game);
+            assert_eq!(record.result(), result);
+            assert_eq!(record.header(HEADER_TERMINATION), Some(termination));
+            let replayed = GameRecord::read(&record.to_string()).unwrap().replay().unwrap();
+            assert_eq!(replayed.state(), state);
+        }
+    }
+
+    #[test]
+    fn test_replay_rejects_termination_that_doesnt_fit() {
+        // Player 1 is to move, so only they can have run out of time.
+        let record = GameRecord::read("[Termination \"time forfeit\"]\n1. 4 4 1-0\n").unwrap();
+        assert!(matches!(record.replay(), Err(RecordError::Malformed { .. })));
+        // The game was won on the board before anyone could resign.
+        let text = "[Termination \"resignation\"]\n1. 1 2 2. 1 2 3. 1 2 4. 1 0-1\n";
+        let record = GameRecord::read(text).unwrap();
+        assert!(matches!(record.replay(), Err(RecordError::Malformed { .. })));
+        let record = GameRecord::read("[Termination \"normal\"]\n1. 4 *\n").unwrap();
+        assert_eq!(record.replay().unwrap().state(), GameState::InProgress);
+    }
+
+    #[test]
+    fn test_set_result_updates_header() {
+        let mut record = GameRecord::from_game(&ConnectFourGame::new(GameConfig::default()));
+        assert_eq!(record.header(HEADER_RESULT), Some("*"));
+        record.set_result(RecordResult::Win(Player::Two));
+        assert_eq!(record.header(HEADER_RESULT), Some("0-1"));
+        assert!(record.to_string().ends_with("0-1\n\n"));
+    }
+
+    #[test]
+    fn test_header_escaping_round_trip() {
+        let mut record = GameRecord::from_game(&ConnectFourGame::new(GameConfig::default()));
+        record.set_header(HEADER_PLAYER1, "Zach \"the \\ Dropper\"");
+        let read = GameRecord::read(&record.to_string()).unwrap();
+        assert_eq!(read.header(HEADER_PLAYER1), Some("Zach \"the \\ Dropper\""));
+        record.set_header(HEADER_EVENT, "Club night\nRound 2");
+        let read = GameRecord::read(&record.to_string()).unwrap();
+        assert_eq!(read.header(HEADER_EVENT), Some("Club night\nRound 2"));
+    }
+
+    #[test]
+    fn test_comment_escaping_round_trip() {
+        let game = notation::parse(GameConfig::default(), "44").unwrap();
+        let comments = ["Opens {the centre}", "A } then a {", "Back\\slash", "Two\nlines"];
+        let mut archive = String::new();
+        for comment in comments {
+            let mut record = GameRecord::from_game(&game);
+            record.moves[0].comment = Some(String::from(comment));
+            archive.push_str(&record.to_string());
+        }
+        let records = read_archive(&archive).unwrap();
+        assert_eq!(records.len(), comments.len());
+        for (record, comment) in records.iter().zip(comments) {
+            assert_eq!(record.moves[0].comment.as_deref(), Some(comment));
+            assert_eq!(record.moves.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_read_archive() {
+        let game = notation::parse(GameConfig::default(), "4444").unwrap();
+        let archive = format!("{}{}", EXAMPLE, GameRecord::from_game(&game));
+        let records = read_archive(&archive).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].moves.len(), 4);
+        assert_eq!(records[1].result(), RecordResult::Unfinished);
+    }
+
+    #[test]
+    fn test_read_multiline_movetext_with_comment() {
+        let text = "[Result \"1/2-1/2\"]\n1. 4 {A comment\nthat spans lines} 4\n2. 4 1/2-1/2\n";
+        let record = GameRecord::read(text).unwrap();
+        assert_eq!(record.moves.len(), 3);
+        assert_eq!(record.result(), RecordResult::Draw);
+        assert_eq!(record.config(), Ok(GameConfig::default()));
+    }
+
+    #[test]
+    fn test_read_rejects_missing_result() {
+        assert!(matches!(GameRecord::read("[Event \"x\"]\n1. 4 4\n"),
+                         Err(RecordError::Malformed { .. })));
+    }
+
+    #[test]
+    fn test_read_rejects_bad_token() {
+        assert!(matches!(GameRecord::read("1. 4 x 1-0\n"),
+                         Err(RecordError::Malformed { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_replay_reports_illegal_move() {
+        let record = GameRecord::read("1. 1 1 2. 1 1 3. 1 1 4. 1 *\n").unwrap();
+        assert_eq!(record.replay().err(), Some(RecordError::IllegalMove {
+            move_number: 7, column: 1, error: MoveError::ColumnFull }));
+    }
+
+    #[test]
+    fn test_invalid_variant() {
+        let record = GameRecord::read("[Variant \"huge\"]\n*\n").unwrap();
+        assert_eq!(record.config(), Err(RecordError::InvalidVariant(String::from("huge"))));
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19561), (2023, 7, 23));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    }
+}
@@ -0,0 +1,229 @@
+// How the game is shown to the players.
+//
+// The game loop reports what happens through a `Renderer` and never writes output itself, so
+// the same loop can draw to a terminal, write plain text, log a game or record it for a test.
+
+use crate::clock;
+use crate::game::ConnectFourGame;
+use crate::game::GameState;
+use crate::game::MoveError;
+use crate::game::Player;
+use crate::notation;
+use std::io::Write;
+use std::time::Duration;
+
+
+const CELL_EMPTY: char = '.';
+const CELL_P1: char = 'X';
+const CELL_P2: char = 'O';
+
+
+pub trait Renderer {
+    // Show the board: at the start of every turn, and after an undo, redo or load.
+    fn board_updated(&mut self, game: &ConnectFourGame);
+
+
+    // Show the time each player has left, player 1's first: at the start of every turn of a
+    // game played on a clock.
+    fn clock_updated(&mut self, times: [Duration; 2]);
+
+
+    // Ask the player to move for their move, mentioning any draw the other player has offered.
+    // Only called when a person is choosing it.
+    fn prompt(&mut self, game: &ConnectFourGame);
+
+
+    // Explain why the move into the given column (0-indexed) couldn't be played.
+    fn invalid_move(&mut self, col: usize, error: MoveError);
+
+
+    // Show how the game ended. The state is the one the game ended in.
+    fn game_over(&mut self, game: &ConnectFourGame, state: GameState);
+
+
+    // Show any other message, such as the computer thinking or a failed load.
+    fn message(&mut self, text: &str);
+}
+
+
+// A renderer that writes plain text with no colours or escape codes, one line per row, so the
+// output can be logged, piped or compared in a test. Player 1's tokens are X and player 2's
+// are O.
+pub struct TextRenderer<W: Write> {
+    out: W,
+}
+
+
+impl<W: Write> TextRenderer<W> {
+    pub fn new(out: W) -> Self {
+        return TextRenderer { out };
+    }
+
+
+    pub fn into_inner(self) -> W {
+        return self.out;
+    }
+
+
+    // Write a line of output. Rendering is best effort: a closed or full output shouldn't stop
+    // the game, so write errors are ignored.
+    fn line(&mut self, text: &str) {
+        let _ = writeln!(self.out, "{text}");
+    }
+}
+
+
+impl<W: Write> Renderer for TextRenderer<W> {
+    fn board_updated(&mut self, game: &ConnectFourGame) {
+        let board: Vec<Vec<Player>> = game.board();
+        for row in (0..game.config().height()).rev() {
+            let cells: Vec<String> = board.iter()
+                    .map(|column| cell_char(column.get(row).copied()).to_string())
+                    .collect();
+            self.line(&cells.join(" "));
+        }
+        let numbers: Vec<String> =
+                (1..=game.config().width()).map(|col| (col % 10).to_string()).collect();
+        self.line(&numbers.join(" "));
+    }
+
+
+    fn clock_updated(&mut self, times: [Duration; 2]) {
+        self.line(&format!("Time left: {} {}, {} {}",
+                           player_name(Player::One), clock::format_time(times[0]),
+                           player_name(Player::Two), clock::format_time(times[1])));
+    }
+
+
+    fn prompt(&mut self, game: &ConnectFourGame) {
+        let player: Player = game.active_player();
+        if game.draw_offer() == Some(player.other()) {
+            self.line(&format!("{} offers a draw.", player_name(player.other())));
+        }
+        self.line(&format!("{} ({}) to move, 1-{}:",
+                           player_name(player), cell_char(Some(player)), game.config().width()));
+    }
+
+
+    fn invalid_move(&mut self, col: usize, error: MoveError) {
+        self.line(&describe_move_error(error, col));
+    }
+
+
+    fn game_over(&mut self, game: &ConnectFourGame, state: GameState) {
+        match state {
+            GameState::Win(winner) => self.line(&format!("{} wins.", player_name(winner))),
+            GameState::OutOfTime(loser) => self.line(&format!("{} ran out of time. {} wins.",
+                    player_name(loser), player_name(loser.other()))),
+            GameState::Resigned(loser) => self.line(&format!("{} resigns. {} wins.",
+                    player_name(loser), player_name(loser.other()))),
+            GameState::DrawAgreed => self.line("Draw agreed."),
+            GameState::Aborted => self.line("Game aborted."),
+            GameState::Tie | GameState::InProgress => self.line("It's a tie."),
+        }
+        if let Ok(moves) = notation::serialize(game) {
+            self.line(&format!("Moves: {moves}"));
+        }
+    }
+
+
+    fn message(&mut self, text: &str) {
+        self.line(text);
+    }
+}
+
+
+// Return a message explaining why a move in the given column was rejected.
+pub fn describe_move_error(error: MoveError, col: usize) -> String {
+    return match error {
+        MoveError::ColumnFull => format!("Column {} is full. Try another column.", col + 1),
+        MoveError::ColumnOutOfRange => format!("There is no column {}.", col + 1),
+        MoveError::GameAlreadyOver => String::from("The game is already over."),
+        MoveError::NotYourTurn => String::from("It's not your turn."),
+    };
+}
+
+
+fn player_name(player: Player) -> &'static str {
+    return match player {
+        Player::One => "Player 1",
+        Player::Two => "Player 2",
+    };
+}
+
+
+fn cell_char(cell: Option<Player>) -> char {
+    return match cell {
+        Some(Player::One) => CELL_P1,
+        Some(Player::Two) => CELL_P2,
+        None => CELL_EMPTY,
+    };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameConfig;
+
+    fn rendered(render: impl FnOnce(&mut TextRenderer<Vec<u8>>)) -> String {
+        let mut renderer = TextRenderer::new(Vec::new());
+        render(&mut renderer);
+        return String::from_utf8(renderer.into_inner()).unwrap();
+    }
+
+    #[test]
+    fn test_text_board() {
+        let game = notation::parse(GameConfig::new(4, 3, 3).unwrap(), "1121").unwrap();
+        let text = rendered(|renderer| renderer.board_updated(&game));
+        assert_eq!(text, "O . . .\nO . . .\nX X . .\n1 2 3 4\n");
+    }
+
+    #[test]
+    fn test_text_prompt() {
+        let game = notation::parse(GameConfig::default(), "4").unwrap();
+        assert_eq!(rendered(|renderer| renderer.prompt(&game)), "Player 2 (O) to move, 1-7:\n");
+    }
+
+    #[test]
+    fn test_text_game_over() {
+        let game = notation::parse(GameConfig::default(), "1212121").unwrap();
+        let text = rendered(|renderer| renderer.game_over(&game, game.state()));
+        assert_eq!(text, "Player 1 wins.\nMoves: 1212121\n");
+    }
+
+    #[test]
+    fn test_text_clock_and_loss_on_time() {
+        let times = [Duration::from_secs(300), Duration::from_millis(4_250)];
+        let text = rendered(|renderer| renderer.clock_updated(times));
+        assert_eq!(text, "Time left: Player 1 5:00, Player 2 0:04.2\n");
+        let mut game = notation::parse(GameConfig::default(), "4").unwrap();
+        let state = game.lose_on_time();
+        let text = rendered(|renderer| renderer.game_over(&game, state));
+        assert_eq!(text, "Player 2 ran out of time. Player 1 wins.\nMoves: 4\n");
+    }
+
+    #[test]
+    fn test_text_draw_offer_and_endings() {
+        let mut game = notation::parse(GameConfig::default(), "4").unwrap();
+        game.offer_draw(Player::One);
+        assert_eq!(rendered(|renderer| renderer.prompt(&game)),
+                   "Player 1 offers a draw.\nPlayer 2 (O) to move, 1-7:\n");
+        let endings = [
+            (GameState::Resigned(Player::Two), "Player 2 resigns. Player 1 wins."),
+            (GameState::DrawAgreed, "Draw agreed."),
+            (GameState::Aborted, "Game aborted."),
+        ];
+        for (state, text) in endings {
+            let output = rendered(|renderer| renderer.game_over(&game, state));
+            assert_eq!(output, format!("{text}\nMoves: 4\n"));
+        }
+    }
+
+    #[test]
+    fn test_describe_move_error() {
+        assert_eq!(describe_move_error(MoveError::ColumnFull, 2),
+                   "Column 3 is full. Try another column.");
+        assert_eq!(describe_move_error(MoveError::ColumnOutOfRange, 7), "There is no column 8.");
+    }
+}
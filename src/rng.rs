@@ -0,0 +1,89 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+
+// A small, fast pseudo-random number generator (xorshift64*). Good enough for choosing moves
+// in playouts and varying the computer's play, but not for anything that needs to be secure.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+
+impl Rng {
+    // Create a generator that always produces the same numbers for the same seed.
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero, or every number after it would be zero too.
+        return Rng { state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed } };
+    }
+
+
+    // Create a generator seeded from the system clock, so it differs from run to run.
+    pub fn from_time() -> Self {
+        let nanos: u128 = SystemTime::now().duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos());
+        return Rng::new(nanos as u64);
+    }
+
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        return self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+
+    // Return a number in 0..n, which must not be empty.
+    pub fn below(&mut self, n: usize) -> usize {
+        return (self.next_u64() % n as u64) as usize;
+    }
+
+
+    // Return true with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        return ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_zero_seed() {
+        let mut rng = Rng::new(0);
+        assert_ne!(rng.next_u64(), rng.next_u64());
+    }
+
+    #[test]
+    fn test_below() {
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[rng.below(7)] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+    }
+
+    #[test]
+    fn test_chance() {
+        let mut rng = Rng::new(7);
+        assert!(!(0..100).any(|_| rng.chance(0.0)));
+        assert!((0..100).all(|_| rng.chance(1.0)));
+        let hits = (0..10000).filter(|_| rng.chance(0.25)).count();
+        assert!((2000..3000).contains(&hits));
+    }
+}
@@ -0,0 +1,336 @@
+// Saving and loading games in progress.
+//
+// A saved game is a text file with one `key value` entry per line, written in this order:
+//
+//     rust-connect-4 save 2
+//     width 7
+//     height 6
+//     win 4
+//     to-move 2
+//     moves 4 4 5
+//     redo 3 1
+//     result offer 1
+//
+// The first line identifies the format and its version. `width`, `height` and `win` are the
+// game config. `moves` lists the columns played so far in order and `redo` lists the moves that
+// were taken back and can still be redone, next one first; both use 1-indexed columns and may
+// be empty. `to-move` is the player to move (1 or 2), which must agree with the moves. `result`
+// is how the game ended if it didn't end on the board: `time P` or `resign P` when player P ran
+// out of time or resigned, `draw` when the players agreed to a draw, or `abort` when the game
+// was abandoned. In a game still in progress it's `offer P` when player P has offered a draw
+// that hasn't been answered, and otherwise empty, as it is for a game won or tied on the board.
+// Version 1 saves, which have no `result`, can still be read. Blank lines and lines starting
+// with '#' are ignored.
+
+use crate::game::ConnectFourGame;
+use crate::game::GameConfig;
+use crate::game::GameState;
+use crate::game::MoveError;
+use crate::game::Player;
+use std::fs;
+use std::io;
+
+
+const FORMAT_HEADER: &str = "rust-connect-4 save 2";
+const FORMAT_HEADER_V1: &str = "rust-connect-4 save 1";  // The same, without the result.
+
+
+// The reasons a game can fail to save or load.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    // The given line (1-indexed) of the file is malformed, or a required entry is missing.
+    Malformed { line: usize, message: String },
+    // A saved move can't be replayed.
+    IllegalMove { column: usize, error: MoveError },
+}
+
+
+// Write the given game in the save format.
+pub fn write(game: &ConnectFourGame) -> String {
+    let config: GameConfig = game.config();
+    let result: String = match (game.state(), game.draw_offer()) {
+        (GameState::OutOfTime(player), _) => format!(" time {}", player_number(player)),
+        (GameState::Resigned(player), _) => format!(" resign {}", player_number(player)),
+        (GameState::DrawAgreed, _) => String::from(" draw"),
+        (GameState::Aborted, _) => String::from(" abort"),
+        (_, Some(player)) => format!(" offer {}", player_number(player)),
+        (_, None) => String::new(),
+    };
+    return format!("{FORMAT_HEADER}\nwidth {}\nheight {}\nwin {}\nto-move {}\nmoves{}\nredo{}\n\
+                    result{}\n",
+                   config.width(), config.height(), config.win_length(),
+                   player_number(game.active_player()), format_columns(game.history()),
+                   format_columns(&game.redo_moves()), result);
+}
+
+
+// Read a game from the save format, replaying its moves.
+pub fn read(text: &str) -> Result<ConnectFourGame, SaveError> {
+    let lines: Vec<(usize, &str)> = text.lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+    let mut entries = lines.iter();
+    let has_result: bool = match entries.next() {
+        Some((_, header)) if *header == FORMAT_HEADER => true,
+        Some((_, header)) if *header == FORMAT_HEADER_V1 => false,
+        Some((line, _)) => return Err(malformed(*line, "not a rust-connect-4 save")),
+        None => return Err(malformed(0, "empty file")),
+    };
+    let width: usize = parse_number(read_entry(&mut entries, "width")?)?;
+    let height: usize = parse_number(read_entry(&mut entries, "height")?)?;
+    let win_length: usize = parse_number(read_entry(&mut entries, "win")?)?;
+    let to_move: (usize, &str) = read_entry(&mut entries, "to-move")?;
+    let moves: Vec<usize> = parse_columns(read_entry(&mut entries, "moves")?)?;
+    let redo: Vec<usize> = parse_columns(read_entry(&mut entries, "redo")?)?;
+    let result: (usize, &str) =
+            if has_result { read_entry(&mut entries, "result")? } else { (0, "") };
+    if let Some((line, _)) = entries.next() {
+        return Err(malformed(*line, "unexpected entry"));
+    }
+
+    let config: GameConfig = GameConfig::new(width, height, win_length)
+            .map_err(|e| malformed(0, &format!("invalid config: {:?}", e)))?;
+    let mut game: ConnectFourGame = ConnectFourGame::new(config);
+    // Play the redo moves after the others and then take them back, which rebuilds the redo
+    // stack exactly as it was.
+    for col in moves.iter().chain(redo.iter()) {
+        game.play_move(game.active_player(), col - 1)
+                .map_err(|error| SaveError::IllegalMove { column: *col, error })?;
+    }
+    for _ in &redo {
+        game.undo();
+    }
+    if to_move.1 != player_number(game.active_player()).to_string() {
+        return Err(malformed(to_move.0, "player to move doesn't match the moves"));
+    }
+    restore_result(&mut game, result)?;
+    return Ok(game);
+}
+
+
+// Save the given game to a file, replacing any existing file.
+pub fn save_to_file(game: &ConnectFourGame, path: &str) -> Result<(), SaveError> {
+    return fs::write(path, write(game)).map_err(SaveError::Io);
+}
+
+
+// Load a game saved by `save_to_file`.
+pub fn load_from_file(path: &str) -> Result<ConnectFourGame, SaveError> {
+    let text: String = fs::read_to_string(path).map_err(SaveError::Io)?;
+    return read(&text);
+}
+
+
+// End the replayed game as the result entry says it ended, or restore its draw offer. Only a
+// game still in progress on the board can have a result, and only the player to move can have
+// run out of time.
+fn restore_result(game: &mut ConnectFourGame, entry: (usize, &str)) -> Result<(), SaveError> {
+    let words: Vec<&str> = entry.1.split_whitespace().collect();
+    if words.is_empty() {
+        return Ok(());
+    }
+    if game.is_over() {
+        return Err(malformed(entry.0, "the game is already over on the board"));
+    }
+    let player = |word: &str| match word {
+        "1" => Ok(Player::One),
+        "2" => Ok(Player::Two),
+        _ => Err(malformed(entry.0, "expected a player number")),
+    };
+    match words.as_slice() {
+        ["time", number] => {
+            if player(number)? != game.active_player() {
+                return Err(malformed(entry.0, "only the player to move can run out of time"));
+            }
+            game.lose_on_time();
+        }
+        ["resign", number] => {
+            game.resign(player(number)?);
+        }
+        ["draw"] => {
+            game.offer_draw(game.active_player());
+            game.accept_draw(game.active_player().other());
+        }
+        ["abort"] => {
+            game.abort();
+        }
+        ["offer", number] => game.offer_draw(player(number)?),
+        _ => return Err(malformed(entry.0, "expected a result")),
+    }
+    return Ok(());
+}
+
+
+fn player_number(player: Player) -> usize {
+    return match player {
+        Player::One => 1,
+        Player::Two => 2,
+    };
+}
+
+
+// Return the given 0-indexed columns as 1-indexed numbers, each preceded by a space.
+fn format_columns(cols: &[usize]) -> String {
+    return cols.iter().map(|col| format!(" {}", col + 1)).collect();
+}
+
+
+// Return the value of the next entry, which must have the given key, with its line number.
+fn read_entry<'a, I>(entries: &mut I, key: &str) -> Result<(usize, &'a str), SaveError>
+        where I: Iterator<Item = &'a (usize, &'a str)> {
+    let (line, text) = match entries.next() {
+        Some(entry) => *entry,
+        None => return Err(malformed(0, &format!("missing {key}"))),
+    };
+    let (found_key, value) = text.split_once(' ').unwrap_or((text, ""));
+    if found_key != key {
+        return Err(malformed(line, &format!("expected {key}")));
+    }
+    return Ok((line, value.trim()));
+}
+
+
+fn parse_number(entry: (usize, &str)) -> Result<usize, SaveError> {
+    return entry.1.parse::<usize>().map_err(|_| malformed(entry.0, "expected a number"));
+}
+
+
+// Parse a list of 1-indexed columns.
+fn parse_columns(entry: (usize, &str)) -> Result<Vec<usize>, SaveError> {
+    return entry.1.split_whitespace()
+            .map(|col| match col.parse::<usize>() {
+                Ok(col) if col > 0 => Ok(col),
+                _ => Err(malformed(entry.0, "expected a column number")),
+            })
+            .collect();
+}
+
+
+fn malformed(line: usize, message: &str) -> SaveError {
+    return SaveError::Malformed { line, message: String::from(message) };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::notation;
+
+    #[test]
+    fn test_write() {
+        let mut game = notation::parse(GameConfig::default(), "44531").unwrap();
+        game.undo();
+        game.undo();
+        assert_eq!(write(&game),
+                   "rust-connect-4 save 2\nwidth 7\nheight 6\nwin 4\nto-move 2\nmoves 4 4 5\n\
+                    redo 3 1\nresult\n");
+    }
+
+    #[test]
+    fn test_round_trip_results() {
+        let endings: [fn(&mut ConnectFourGame); 5] = [
+            |game| { game.lose_on_time(); },
+            |game| { game.resign(Player::One); },
+            |game| {
+                game.offer_draw(Player::Two);
+                game.accept_draw(Player::One);
+            },
+            |game| { game.abort(); },
+            |game| game.offer_draw(Player::One),
+        ];
+        for end in endings {
+            let mut game = notation::parse(GameConfig::default(), "445").unwrap();
+            end(&mut game);
+            let loaded = read(&write(&game)).unwrap();
+            assert_eq!(loaded.state(), game.state());
+            assert_eq!(loaded.draw_offer(), game.draw_offer());
+            assert_eq!(loaded.history(), game.history());
+        }
+    }
+
+    #[test]
+    fn test_read_rejects_result_of_finished_game() {
+        let text = "rust-connect-4 save 2\nwidth 7\nheight 6\nwin 4\nto-move 2\n\
+                    moves 1 2 1 2 1 2 1\nredo\nresult resign 2\n";
+        assert!(matches!(read(text), Err(SaveError::Malformed { line: 8, .. })));
+        let text = "rust-connect-4 save 2\nwidth 7\nheight 6\nwin 4\nto-move 2\nmoves 4\nredo\n\
+                    result time 1\n";
+        assert!(matches!(read(text), Err(SaveError::Malformed { line: 8, .. })));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut game = notation::parse(GameConfig::new(9, 7, 5).unwrap(), "4455667").unwrap();
+        game.undo();
+        let loaded = read(&write(&game)).unwrap();
+        assert_eq!(loaded.config(), game.config());
+        assert_eq!(loaded.history(), game.history());
+        assert_eq!(loaded.redo_moves(), game.redo_moves());
+        assert_eq!(loaded.active_player(), Player::One);
+    }
+
+    #[test]
+    fn test_read_empty_game_with_comments() {
+        let text = "# A new game.\nrust-connect-4 save 1\nwidth 7\nheight 6\nwin 4\n\n\
+                    to-move 1\nmoves\nredo\n";
+        let game = read(text).unwrap();
+        assert!(game.history().is_empty());
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn test_read_finished_game() {
+        let text = "rust-connect-4 save 1\nwidth 7\nheight 6\nwin 4\nto-move 2\n\
+                    moves 1 2 1 2 1 2 1\nredo\n";
+        assert_eq!(read(text).unwrap().state(), GameState::Win(Player::One));
+    }
+
+    #[test]
+    fn test_read_rejects_wrong_header() {
+        assert!(matches!(read("hello\n"), Err(SaveError::Malformed { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_read_rejects_missing_entry() {
+        let text = "rust-connect-4 save 1\nwidth 7\nheight 6\nto-move 1\nmoves\nredo\n";
+        assert!(matches!(read(text), Err(SaveError::Malformed { line: 4, .. })));
+    }
+
+    #[test]
+    fn test_read_rejects_wrong_player_to_move() {
+        let text = "rust-connect-4 save 1\nwidth 7\nheight 6\nwin 4\nto-move 1\nmoves 4\nredo\n";
+        assert!(matches!(read(text), Err(SaveError::Malformed { line: 5, .. })));
+    }
+
+    #[test]
+    fn test_read_rejects_illegal_move() {
+        let text = "rust-connect-4 save 1\nwidth 7\nheight 6\nwin 4\nto-move 1\n\
+                    moves 1 1 1 1 1 1 1 2\nredo\n";
+        assert!(matches!(read(text),
+                         Err(SaveError::IllegalMove { column: 1, error: MoveError::ColumnFull })));
+        let text = "rust-connect-4 save 1\nwidth 7\nheight 6\nwin 4\nto-move 1\nmoves 8\nredo\n";
+        assert!(matches!(read(text), Err(SaveError::IllegalMove {
+                column: 8, error: MoveError::ColumnOutOfRange })));
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let path = std::env::temp_dir().join("rust_connect_4_test_save_and_load_file.txt");
+        let path = path.to_str().unwrap();
+        let game = notation::parse(GameConfig::default(), "4453").unwrap();
+        save_to_file(&game, path).unwrap();
+        let loaded = load_from_file(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded.history(), game.history());
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        assert!(matches!(load_from_file("/nonexistent/rust-connect-4/save.txt"),
+                         Err(SaveError::Io(_))));
+    }
+}
@@ -0,0 +1,579 @@
+use crate::game::GameConfig;
+use crate::game::Player;
+use crate::negamax::move_order;
+use crate::position::Bitboard;
+use crate::position::Position;
+use crate::position::zobrist_key;
+use crate::transposition::Bound;
+use crate::transposition::Entry;
+use crate::transposition::Replacement;
+use crate::transposition::TranspositionTable;
+use std::cmp::Reverse;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+
+
+const CELLS: i32 = (WIDTH * HEIGHT) as i32;
+const DEADLINE_CHECK_INTERVAL: u64 = 1 << 12;  // Positions searched between looks at the time.
+const DEFAULT_TABLE_MEMORY: usize = 64 << 20;
+// The score of the empty board, which takes far longer to solve than any other position: the
+// first player wins with their 21st token.
+const EMPTY_BOARD_SCORE: i32 = 1;
+const HEIGHT: usize = 6;
+// How long a computer player gives the solver to find a move before searching a fixed number of
+// moves ahead instead. Positions early in the game can take minutes to solve.
+pub const MOVE_TIME_LIMIT: Duration = Duration::from_secs(3);
+const WIDTH: usize = 7;
+
+
+// The result of a game with perfect play, from the point of view of the player to move.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+
+// The exact game-theoretic value of a position.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Solution {
+    pub outcome: Outcome,
+    // The number of moves, counting both players, until the game ends with perfect play. The
+    // winner wins as fast as possible and the loser holds out as long as possible.
+    pub moves: usize,
+    // The score in the usual solver convention: positive when the player to move wins, higher
+    // the sooner they win, and zero for a draw.
+    pub score: i32,
+}
+
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum SolveError {
+    // The solver only handles the standard 7x6 board with a win length of 4.
+    UnsupportedConfig,
+    // The game has already been won or the board is full.
+    GameOver,
+    // The search didn't finish within its time limit.
+    OutOfTime,
+}
+
+
+// Return the exact value of the given position with perfect play by both sides.
+pub fn solve(position: &Position) -> Result<Solution, SolveError> {
+    return Solver::new().solve(position);
+}
+
+
+// A perfect-play solver for the standard board. It keeps a transposition table between calls,
+// so reusing one solver for a sequence of related positions is much faster than calling
+// `solve` for each.
+pub struct Solver {
+    table: TranspositionTable,  // Holds upper bounds on the scores of positions.
+    deadline: Option<Instant>,  // When the search in progress must stop, if it has a limit.
+    out_of_time: bool,  // Whether the search in progress passed its deadline.
+    nodes: u64,  // The positions searched so far, for spacing out looks at the time.
+}
+
+
+impl Default for Solver {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+
+impl Solver {
+    pub fn new() -> Self {
+        return Solver::with_table(DEFAULT_TABLE_MEMORY, Replacement::Always);
+    }
+
+
+    // Create a solver whose transposition table uses about the given number of bytes and the
+    // given replacement policy.
+    pub fn with_table(table_memory: usize, replacement: Replacement) -> Self {
+        return Solver {
+            table: TranspositionTable::new(table_memory, replacement),
+            deadline: None,
+            out_of_time: false,
+            nodes: 0,
+        };
+    }
+
+
+    // Return the exact value of the given position with perfect play by both sides.
+    pub fn solve(&mut self, position: &Position) -> Result<Solution, SolveError> {
+        let board: Board = Board::from_position(position)?;
+        if board.moves == 0 {
+            return Ok(solution(EMPTY_BOARD_SCORE, 0));
+        }
+        let score: i32 = self.solve_board(&board);
+        return Ok(solution(score, board.moves));
+    }
+
+
+    // Return a best column for the player to move: a fastest win if there is one, otherwise
+    // a draw, otherwise the move that holds out longest.
+    pub fn best_move(&mut self, position: &Position) -> Result<usize, SolveError> {
+        return self.find_best_move(position);
+    }
+
+
+    // Return a best column as `best_move` does, or `SolveError::OutOfTime` if finding it takes
+    // longer than the given time. What was learned before time ran out is kept, which speeds up
+    // later searches.
+    pub fn best_move_within(&mut self, position: &Position, limit: Duration)
+            -> Result<usize, SolveError> {
+        self.deadline = Some(Instant::now() + limit);
+        let result: Result<usize, SolveError> = self.find_best_move(position);
+        self.deadline = None;
+        if self.out_of_time {
+            self.out_of_time = false;
+            return Err(SolveError::OutOfTime);
+        }
+        return result;
+    }
+
+
+    fn find_best_move(&mut self, position: &Position) -> Result<usize, SolveError> {
+        let board: Board = Board::from_position(position)?;
+        if board.moves == 0 {
+            // The empty board takes far longer to solve than any other position, and its answer
+            // is well known: the first player wins by starting in the center.
+            return Ok(WIDTH / 2);
+        }
+        let order: Vec<usize> = move_order(WIDTH);
+        if let Some(col) = order.iter().find(|col| board.is_winning_move(**col)) {
+            return Ok(*col);
+        }
+        let next: u64 = board.non_losing_moves();
+        if next == 0 {
+            // Every move loses at once, so any will do.
+            return Ok(*order.iter().find(|col| board.can_play(**col)).unwrap());
+        }
+        // Once the score is known, a best move is one whose position is no better than that
+        // for the opponent, which a null-window search can check.
+        let score: i32 = self.solve_board(&board);
+        for col in &order {
+            let column_move: u64 = next & column_mask(*col);
+            if column_move == 0 {
+                continue;
+            }
+            let mut child: Board = board;
+            child.play(column_move);
+            let child_score: i32 = self.negamax(&child, -score, -score + 1);
+            if self.out_of_time {
+                return Err(SolveError::OutOfTime);
+            }
+            if child_score <= -score {
+                return Ok(*col);
+            }
+        }
+        unreachable!("some move must achieve the score of the position");
+    }
+
+
+    // Find the exact score by narrowing the range it could be in with null-window searches,
+    // each of which only answers whether the score is above a given value.
+    fn solve_board(&mut self, board: &Board) -> i32 {
+        if board.can_win_next() {
+            return (CELLS + 1 - board.moves as i32) / 2;
+        }
+        let mut min: i32 = -(CELLS - board.moves as i32) / 2;
+        let mut max: i32 = (CELLS + 1 - board.moves as i32) / 2;
+        while min < max {
+            let mut med: i32 = min + (max - min) / 2;
+            // Probe near zero first, since most positions are close to a draw.
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let score: i32 = self.negamax(board, med, med + 1);
+            if self.out_of_time {
+                break;  // The score is unknown, and the caller reports that.
+            }
+            if score <= med {
+                max = score;
+            } else {
+                min = score;
+            }
+        }
+        return min;
+    }
+
+
+    // Return the score of the position if it lies in (alpha, beta), otherwise a bound on it.
+    // The player to move must not be able to win immediately. Once the deadline has passed, the
+    // search unwinds without storing anything, and the score it returns means nothing.
+    fn negamax(&mut self, board: &Board, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.out_of_time = true;
+        }
+        if self.out_of_time {
+            return alpha;
+        }
+        let next: u64 = board.non_losing_moves();
+        if next == 0 {
+            return -(CELLS - board.moves as i32) / 2;  // Every move lets the opponent win.
+        }
+        if board.moves as i32 >= CELLS - 2 {
+            return 0;  // Neither player can win with the last two moves.
+        }
+        let min: i32 = -(CELLS - 2 - board.moves as i32) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        let mut max: i32 = (CELLS - 1 - board.moves as i32) / 2;
+        if let Some(entry) = self.table.get(board.hash) {
+            max = entry.score;
+        }
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        // Try the moves that create the most new winning cells first.
+        let mut moves: Vec<(u64, u32)> = Vec::with_capacity(WIDTH);
+        for col in move_order(WIDTH) {
+            let column_move: u64 = next & column_mask(col);
+            if column_move != 0 {
+                moves.push((column_move, board.move_score(column_move)));
+            }
+        }
+        // The sort is stable, so moves with equal scores stay in center-first order.
+        moves.sort_by_key(|(_, score)| Reverse(*score));
+        for (column_move, _) in moves {
+            let mut child: Board = *board;
+            child.play(column_move);
+            let score: i32 = -self.negamax(&child, -beta, -alpha);
+            if self.out_of_time {
+                return alpha;
+            }
+            if score >= beta {
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+        // Every move scored at most alpha, so alpha is an upper bound on the score.
+        self.table.store(Entry {
+            hash: board.hash,
+            depth: (CELLS - board.moves as i32) as u16,  // Earlier positions save more work.
+            score: alpha,
+            bound: Bound::Upper,
+            best_move: None,
+        });
+        return alpha;
+    }
+}
+
+
+// Convert a score for the position after the given number of moves to a solution.
+fn solution(score: i32, moves: usize) -> Solution {
+    let remaining: i32 = CELLS + 1 - moves as i32;
+    if score > 0 {
+        // The score counts the winner's unused moves; see `Solver::solve_board`.
+        let own_moves: i32 = remaining / 2 - score;
+        return Solution { outcome: Outcome::Win, moves: (2 * own_moves + 1) as usize, score };
+    }
+    if score < 0 {
+        let opponent_moves: i32 = (remaining - 1) / 2 + score;
+        return Solution { outcome: Outcome::Loss, moves: (2 * opponent_moves + 2) as usize, score };
+    }
+    return Solution { outcome: Outcome::Draw, moves: CELLS as usize - moves, score };
+}
+
+
+fn bottom_mask() -> u64 {
+    return (0..WIDTH).fold(0, |mask, col| mask | 1 << (col * (HEIGHT + 1)));
+}
+
+
+fn board_mask() -> u64 {
+    return bottom_mask() * ((1 << HEIGHT) - 1);
+}
+
+
+fn column_mask(col: usize) -> u64 {
+    return ((1 << HEIGHT) - 1) << (col * (HEIGHT + 1));
+}
+
+
+// The standard board in the bit layout of `Position`, squeezed into a u64 and stored as the
+// tokens of the player to move plus all tokens, which is the fastest form to search. The
+// Zobrist hash is kept the same as that of the matching `Position`.
+#[derive(Clone)]
+#[derive(Copy)]
+struct Board {
+    current: u64,
+    mask: u64,
+    moves: usize,
+    hash: u64,
+}
+
+
+impl Board {
+    fn from_position(position: &Position) -> Result<Board, SolveError> {
+        if position.config() != GameConfig::default() {
+            return Err(SolveError::UnsupportedConfig);
+        }
+        let to_move = position.to_move();
+        if position.is_full() || position.has_won(to_move) || position.has_won(to_move.other()) {
+            return Err(SolveError::GameOver);
+        }
+        return Ok(Board {
+            current: position.mask(to_move) as u64,
+            mask: (position.mask(to_move) | position.mask(to_move.other())) as u64,
+            moves: position.moves(),
+            hash: position.hash(),
+        });
+    }
+
+
+    fn can_play(&self, col: usize) -> bool {
+        return self.mask & top_mask(col) == 0;
+    }
+
+
+    fn play(&mut self, column_move: u64) {
+        let player: Player = if self.moves.is_multiple_of(2) { Player::One } else { Player::Two };
+        self.hash ^= zobrist_key(player, column_move as Bitboard);
+        self.current ^= self.mask;
+        self.mask |= column_move;
+        self.moves += 1;
+    }
+
+
+    fn is_winning_move(&self, col: usize) -> bool {
+        return self.winning_cells() & self.possible() & column_mask(col) != 0;
+    }
+
+
+    fn can_win_next(&self) -> bool {
+        return self.winning_cells() & self.possible() != 0;
+    }
+
+
+    // Return the cells a token can be dropped into.
+    fn possible(&self) -> u64 {
+        return (self.mask + bottom_mask()) & board_mask();
+    }
+
+
+    // Return the playable cells that don't hand the opponent an immediate win: no move at all
+    // if the opponent has two wins to block, only the block if they have one, and never the
+    // cell right under one of the opponent's winning cells.
+    fn non_losing_moves(&self) -> u64 {
+        let mut possible: u64 = self.possible();
+        let opponent_wins: u64 = winning_cells(self.current ^ self.mask, self.mask);
+        let forced: u64 = possible & opponent_wins;
+        if forced != 0 {
+            if forced & (forced - 1) != 0 {
+                return 0;
+            }
+            possible = forced;
+        }
+        return possible & !(opponent_wins >> 1);
+    }
+
+
+    // Score a move by how many winning cells it gives the player making it.
+    fn move_score(&self, column_move: u64) -> u32 {
+        return winning_cells(self.current | column_move, self.mask).count_ones();
+    }
+
+
+    // Return the empty cells that would complete a line for the player to move.
+    fn winning_cells(&self) -> u64 {
+        return winning_cells(self.current, self.mask);
+    }
+}
+
+
+fn top_mask(col: usize) -> u64 {
+    return 1 << (HEIGHT - 1 + col * (HEIGHT + 1));
+}
+
+
+// Return the empty cells that would complete a line of four for the player with the given
+// tokens, checking each direction for three tokens on either side of the cell.
+fn winning_cells(tokens: u64, mask: u64) -> u64 {
+    // Vertical lines can only be completed from above.
+    let mut cells: u64 = (tokens << 1) & (tokens << 2) & (tokens << 3);
+    for shift in [HEIGHT + 1, HEIGHT, HEIGHT + 2] {  // Horizontal and the two diagonals.
+        let mut pair: u64 = (tokens << shift) & (tokens << (2 * shift));
+        cells |= pair & (tokens << (3 * shift));
+        cells |= pair & (tokens >> shift);
+        pair = (tokens >> shift) & (tokens >> (2 * shift));
+        cells |= pair & (tokens << shift);
+        cells |= pair & (tokens >> (3 * shift));
+    }
+    return cells & (board_mask() ^ mask);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation;
+    use crate::notation::test_positions::position;
+
+    #[test]
+    fn test_immediate_win() {
+        let solution = solve(&position("121212")).unwrap();
+        assert_eq!(solution.outcome, Outcome::Win);
+        assert_eq!(solution.moves, 1);
+    }
+
+    #[test]
+    fn test_unstoppable_double_threat() {
+        // Player 1 has two open ends on the bottom row; player 2 can only block one.
+        let solution = solve(&position("44553")).unwrap();
+        assert_eq!(solution.outcome, Outcome::Loss);
+        assert_eq!(solution.moves, 2);
+    }
+
+    #[test]
+    fn test_win_in_three() {
+        // Player 1 makes an open three on the bottom row and wins on their next move.
+        let solution = solve(&position("4455")).unwrap();
+        assert_eq!(solution.outcome, Outcome::Win);
+        assert_eq!(solution.moves, 3);
+    }
+
+    // Return the score of the position by searching every line of play, in the same convention
+    // as the solver. Only feasible for nearly full boards.
+    fn exhaustive_score(position: &mut Position) -> i32 {
+        let config = position.config();
+        let cells = (config.width() * config.height()) as i32;
+        if (0..config.width()).any(|col| position.can_play(col) && position.is_winning_move(col)) {
+            return (cells + 1 - position.moves() as i32) / 2;
+        }
+        let mut best: Option<i32> = None;
+        for col in 0..config.width() {
+            if position.can_play(col) {
+                position.play(col);
+                let score = -exhaustive_score(position);
+                position.undo(col);
+                best = Some(best.map_or(score, |best| best.max(score)));
+            }
+        }
+        return best.unwrap_or(0);
+    }
+
+    #[test]
+    fn test_matches_exhaustive_search() {
+        // Fill the board from a fixed pseudo-random sequence of moves, stopping a few moves
+        // before the end or before anyone can win, and compare against exhaustive search.
+        let mut solver = Solver::new();
+        let mut seed: u64 = 12345;
+        let mut checked = 0;
+        while checked < 20 {
+            let mut position = Position::new(GameConfig::default());
+            while position.moves() < 30 {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let col = (seed >> 33) as usize % 7;
+                if !position.can_play(col) || position.is_winning_move(col) {
+                    continue;
+                }
+                position.play(col);
+                if (0..7).all(|col| !position.can_play(col) || position.is_winning_move(col)) {
+                    break;
+                }
+            }
+            if position.moves() < 30 {
+                continue;
+            }
+            let expected = exhaustive_score(&mut position.clone());
+            assert_eq!(solver.solve(&position).unwrap().score, expected);
+            checked += 1;
+        }
+    }
+
+    #[test]
+    fn test_solution_moves() {
+        // After 30 moves, player 1 to move can still make 6 moves of their own.
+        assert_eq!(solution(6, 30), Solution { outcome: Outcome::Win, moves: 1, score: 6 });
+        assert_eq!(solution(1, 30), Solution { outcome: Outcome::Win, moves: 11, score: 1 });
+        assert_eq!(solution(-6, 30), Solution { outcome: Outcome::Loss, moves: 2, score: -6 });
+        assert_eq!(solution(-1, 30), Solution { outcome: Outcome::Loss, moves: 12, score: -1 });
+        assert_eq!(solution(0, 30), Solution { outcome: Outcome::Draw, moves: 12, score: 0 });
+    }
+
+    #[test]
+    fn test_board_hash_matches_position() {
+        let mut position = position("4453");
+        let mut board = Board::from_position(&position).unwrap();
+        board.play(board.possible() & column_mask(1));
+        position.play(1);
+        assert_eq!(board.hash, position.hash());
+    }
+
+    #[test]
+    fn test_small_table() {
+        let mut solver = Solver::with_table(1 << 10, Replacement::DepthPreferred);
+        let solution = solver.solve(&position("77777766666612121")).unwrap();
+        assert_eq!(solution.outcome, Outcome::Draw);
+    }
+
+    #[test]
+    fn test_best_move_takes_win() {
+        assert_eq!(Solver::new().best_move(&position("121212")), Ok(0));
+    }
+
+    #[test]
+    fn test_best_move_blocks() {
+        // Player 2 must block player 1's vertical line in column 1.
+        let moves = "77777766666612121";
+        assert_eq!(Solver::new().best_move(&position(moves)), Ok(0));
+    }
+
+    #[test]
+    fn test_best_move_opens_in_center() {
+        assert_eq!(Solver::new().best_move(&position("")), Ok(3));
+    }
+
+    #[test]
+    fn test_empty_board() {
+        let solution = solve(&position("")).unwrap();
+        assert_eq!((solution.outcome, solution.moves), (Outcome::Win, 41));
+    }
+
+    #[test]
+    fn test_best_move_within_time_limit() {
+        let mut solver = Solver::new();
+        let limit = Duration::from_millis(50);
+        assert_eq!(solver.best_move_within(&position("4"), limit), Err(SolveError::OutOfTime));
+        assert_eq!(solver.best_move_within(&position("121212"), limit), Ok(0));
+    }
+
+    #[test]
+    fn test_unsupported_config() {
+        let game = notation::parse(GameConfig::new(8, 7, 4).unwrap(), "44").unwrap();
+        assert_eq!(solve(game.position()), Err(SolveError::UnsupportedConfig));
+    }
+
+    #[test]
+    fn test_game_over() {
+        assert_eq!(solve(&position("1212121")), Err(SolveError::GameOver));
+    }
+}
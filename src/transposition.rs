@@ -0,0 +1,174 @@
+// A fixed-size transposition table for caching search results by position hash.
+//
+// Searches reach the same position through different move orders, so remembering what was
+// learned about a position the first time saves searching it again. The table has a fixed
+// number of slots chosen from a memory budget, and each position hash maps to one slot. When
+// two positions want the same slot, the replacement policy decides which one to keep.
+
+use std::mem::size_of;
+use std::vec::Vec;
+
+
+// How a stored score relates to the true score of the position, which depends on whether the
+// search that produced it was cut off by the alpha-beta window.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,  // The true score is at least the stored score.
+    Upper,  // The true score is at most the stored score.
+}
+
+
+// Which entry to keep when a new entry maps to an occupied slot.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Replacement {
+    // Always keep the new entry. Cheap, and favours the positions the search is in now.
+    Always,
+    // Keep the entry that was searched deeper, since it saved more work. An entry for the same
+    // position is always replaced.
+    DepthPreferred,
+}
+
+
+// What a search learned about one position. The fields are kept small so that more entries fit
+// in the memory budget.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Entry {
+    pub hash: u64,
+    pub depth: u16,  // How many moves ahead the search looked, which is how much it's worth.
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<u8>,  // The column to try first when searching the position again.
+}
+
+
+pub struct TranspositionTable {
+    slots: Vec<Option<Entry>>,
+    replacement: Replacement,
+}
+
+
+impl TranspositionTable {
+    // Create a table using about the given number of bytes, with at least one slot.
+    pub fn new(memory: usize, replacement: Replacement) -> Self {
+        let capacity: usize = (memory / size_of::<Option<Entry>>()).max(1);
+        return TranspositionTable { slots: vec![None; capacity], replacement };
+    }
+
+
+    // Return the number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        return self.slots.len();
+    }
+
+
+    pub fn replacement(&self) -> Replacement {
+        return self.replacement;
+    }
+
+
+    // Return the entry for the position with the given hash, if it's still in the table.
+    pub fn get(&self, hash: u64) -> Option<Entry> {
+        return self.slots[self.slot(hash)].filter(|entry| entry.hash == hash);
+    }
+
+
+    // Store the given entry, unless the replacement policy keeps the entry already in its slot.
+    pub fn store(&mut self, entry: Entry) {
+        let slot: usize = self.slot(entry.hash);
+        let replace: bool = match (self.replacement, self.slots[slot]) {
+            (_, None) | (Replacement::Always, _) => true,
+            (Replacement::DepthPreferred, Some(old)) =>
+                    old.hash == entry.hash || entry.depth >= old.depth,
+        };
+        if replace {
+            self.slots[slot] = Some(entry);
+        }
+    }
+
+
+    // Remove every entry.
+    pub fn clear(&mut self) {
+        self.slots.fill(None);
+    }
+
+
+    fn slot(&self, hash: u64) -> usize {
+        return (hash % self.slots.len() as u64) as usize;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(hash: u64, depth: u16, score: i32) -> Entry {
+        return Entry { hash, depth, score, bound: Bound::Exact, best_move: Some(3) };
+    }
+
+    #[test]
+    fn test_capacity_from_memory() {
+        let table = TranspositionTable::new(1 << 20, Replacement::Always);
+        assert_eq!(table.capacity(), (1 << 20) / size_of::<Option<Entry>>());
+        assert_eq!(TranspositionTable::new(0, Replacement::Always).capacity(), 1);
+    }
+
+    #[test]
+    fn test_store_and_get() {
+        let mut table = TranspositionTable::new(1 << 16, Replacement::Always);
+        assert_eq!(table.get(42), None);
+        table.store(entry(42, 5, 10));
+        assert_eq!(table.get(42), Some(entry(42, 5, 10)));
+        assert_eq!(table.get(43), None);
+    }
+
+    #[test]
+    fn test_get_checks_hash_in_shared_slot() {
+        let mut table = TranspositionTable::new(1 << 16, Replacement::Always);
+        let capacity = table.capacity() as u64;
+        table.store(entry(7, 5, 10));
+        assert_eq!(table.get(7 + capacity), None);
+    }
+
+    #[test]
+    fn test_always_replace() {
+        let mut table = TranspositionTable::new(1 << 16, Replacement::Always);
+        let capacity = table.capacity() as u64;
+        table.store(entry(7, 5, 10));
+        table.store(entry(7 + capacity, 1, 20));
+        assert_eq!(table.get(7), None);
+        assert_eq!(table.get(7 + capacity), Some(entry(7 + capacity, 1, 20)));
+    }
+
+    #[test]
+    fn test_depth_preferred_replace() {
+        let mut table = TranspositionTable::new(1 << 16, Replacement::DepthPreferred);
+        let capacity = table.capacity() as u64;
+        table.store(entry(7, 5, 10));
+        table.store(entry(7 + capacity, 1, 20));
+        assert_eq!(table.get(7), Some(entry(7, 5, 10)));
+        table.store(entry(7 + capacity, 6, 30));
+        assert_eq!(table.get(7 + capacity), Some(entry(7 + capacity, 6, 30)));
+        // A new result for the same position replaces the old one even if it's shallower.
+        table.store(entry(7 + capacity, 2, 40));
+        assert_eq!(table.get(7 + capacity), Some(entry(7 + capacity, 2, 40)));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut table = TranspositionTable::new(1 << 16, Replacement::Always);
+        table.store(entry(42, 5, 10));
+        table.clear();
+        assert_eq!(table.get(42), None);
+    }
+}